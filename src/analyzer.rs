@@ -0,0 +1,395 @@
+use crate::section::Section;
+use getset::Getters;
+use std::fmt::{Display, Formatter};
+
+/// A pattern in a `.gitignore` file.
+#[derive(Clone, Debug)]
+struct Pattern {
+    /// The line of the `.gitignore` file that contains the pattern.
+    line: String,
+
+    /// The qualified name of the section that contains the pattern.
+    section: String,
+
+    /// The heading of the section that contains the pattern, which is shown to the user.
+    heading: String,
+
+    /// Whether the pattern re-includes files that an earlier pattern ignored.
+    negated: bool,
+
+    /// Whether the pattern is relative to the `.gitignore` file instead of matching at any level.
+    anchored: bool,
+
+    /// The glob without the negation and the leading and trailing slashes.
+    glob: Vec<char>,
+}
+
+impl Pattern {
+    /// Parses a line of a `.gitignore` file.
+    ///
+    /// `None` is returned for blank lines and comments, which do not contain a pattern.
+    fn parse(line: &str, section: &Section) -> Option<Self> {
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            return None;
+        }
+
+        let (negated, glob) = match trimmed.strip_prefix('!') {
+            Some(glob) => (true, glob),
+            // A leading backslash escapes a `#` or `!` that would otherwise have a special meaning
+            None if trimmed.starts_with("\\#") || trimmed.starts_with("\\!") => {
+                (false, &trimmed[1..])
+            }
+            None => (false, trimmed),
+        };
+
+        let glob = glob.strip_suffix('/').unwrap_or(glob);
+        let anchored = glob.contains('/');
+        let glob = glob.strip_prefix('/').unwrap_or(glob);
+
+        if glob.is_empty() {
+            return None;
+        }
+
+        Some(Pattern {
+            line: String::from(trimmed),
+            section: section.name().clone(),
+            heading: section.heading().clone(),
+            negated,
+            anchored,
+            glob: glob.chars().collect(),
+        })
+    }
+
+    /// Returns whether the pattern ignores the given path or one of its parent directories.
+    ///
+    /// Patterns without a slash match a file or directory at any level, while anchored patterns
+    /// are matched against the full path.
+    fn matches(&self, path: &str) -> bool {
+        let components: Vec<&str> = path.split('/').collect();
+
+        (1..=components.len()).any(|length| {
+            let prefix = &components[..length];
+
+            let candidate = if self.anchored {
+                prefix.join("/")
+            } else {
+                String::from(prefix[length - 1])
+            };
+
+            glob(&self.glob, &candidate.chars().collect::<Vec<char>>())
+        })
+    }
+
+    /// Returns whether the two patterns might match the same file.
+    ///
+    /// The globs are compared by matching each pattern against the other one as if it was a path,
+    /// which catches the common cases like `*.log` and `!keep.log` without solving the general
+    /// problem of intersecting two globs.
+    fn overlaps(&self, other: &Pattern) -> bool {
+        let own: String = self.glob.iter().collect();
+        let other_glob: String = other.glob.iter().collect();
+
+        self.matches(&other_glob) || other.matches(&own)
+    }
+}
+
+/// A conflict between two patterns from different sections of a `.gitignore` file.
+#[derive(Clone, Debug, Eq, PartialEq, Getters)]
+pub struct Conflict {
+    /// Returns the pattern that is overridden.
+    #[getset(get = "pub")]
+    pattern: String,
+
+    /// Returns the section of the pattern that is overridden.
+    #[getset(get = "pub")]
+    section: String,
+
+    /// Returns the later pattern that overrides the first one.
+    #[getset(get = "pub")]
+    overriding_pattern: String,
+
+    /// Returns the section of the pattern that overrides the first one.
+    #[getset(get = "pub")]
+    overriding_section: String,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let effect = if self.overriding_pattern.starts_with('!') {
+            "re-included by"
+        } else {
+            "ignored again by"
+        };
+
+        write!(
+            f,
+            "'{}' in {} is {} '{}' in {}",
+            self.pattern, self.section, effect, self.overriding_pattern, self.overriding_section
+        )
+    }
+}
+
+/// Finds patterns that override each other in a `.gitignore` file.
+///
+/// Git uses the last pattern that matches a file. A negated pattern like `!keep.log` in one
+/// template thus re-includes files that a broader pattern like `*.log` in an earlier template
+/// ignored, and a broader pattern in a later template ignores files that an earlier template
+/// explicitly re-included. Both are easy to miss when combining templates, since each template is
+/// correct on its own.
+#[derive(Debug)]
+pub struct Analyzer {
+    patterns: Vec<Pattern>,
+}
+
+impl Analyzer {
+    /// Returns an analyzer for the given sections of a `.gitignore` file.
+    ///
+    /// The patterns are attributed to the qualified names of their sections, since templates in
+    /// different directories can have the same file name and thus the same heading.
+    pub fn new(sections: &[Section]) -> Self {
+        let patterns = sections
+            .iter()
+            .flat_map(|section| {
+                section
+                    .content()
+                    .lines()
+                    .filter_map(move |line| Pattern::parse(line, section))
+            })
+            .collect();
+
+        Analyzer { patterns }
+    }
+
+    /// Returns the conflicts between patterns in different sections.
+    ///
+    /// Patterns within the same section are not compared, since the authors of a template are
+    /// expected to know how its patterns interact.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+
+        for (i, pattern) in self.patterns.iter().enumerate() {
+            for later in &self.patterns[i + 1..] {
+                if later.section == pattern.section
+                    || later.negated == pattern.negated
+                    || !pattern.overlaps(later)
+                {
+                    continue;
+                }
+
+                conflicts.push(Conflict {
+                    pattern: pattern.line.clone(),
+                    section: pattern.heading.clone(),
+                    overriding_pattern: later.line.clone(),
+                    overriding_section: later.heading.clone(),
+                });
+            }
+        }
+
+        conflicts
+    }
+}
+
+/// Returns whether the glob matches the given text.
+///
+/// The glob supports the wildcards of `.gitignore` files: `*` matches anything except a slash,
+/// `**` matches anything including slashes, `?` matches a single character except a slash, and
+/// `[...]` matches a single character in a set or range. A backslash escapes the next character.
+fn glob(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            let rest = rest.strip_prefix(&['/']).unwrap_or(rest);
+
+            (0..=text.len()).any(|i| glob(rest, &text[i..]))
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+
+            for i in 0..=text.len() {
+                if glob(rest, &text[i..]) {
+                    return true;
+                }
+                if text.get(i) == Some(&'/') {
+                    break;
+                }
+            }
+
+            false
+        }
+        Some('?') => match text.first() {
+            Some(c) if *c != '/' => glob(&pattern[1..], &text[1..]),
+            _ => false,
+        },
+        Some('[') => {
+            let (matched, length) = match text.first() {
+                Some(c) => class(&pattern[1..], *c),
+                None => return false,
+            };
+
+            match length {
+                Some(length) if matched => glob(&pattern[length + 2..], &text[1..]),
+                Some(_length) => false,
+                // An unclosed bracket is matched literally
+                None => text.first() == Some(&'[') && glob(&pattern[1..], &text[1..]),
+            }
+        }
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && glob(&pattern[2..], &text[1..])
+        }
+        Some(c) => text.first() == Some(c) && glob(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Matches a character against a character class.
+///
+/// The class starts after the opening bracket. Returns whether the character is in the class, and
+/// the length of the class up to the closing bracket, which is `None` if the class is not closed.
+fn class(pattern: &[char], c: char) -> (bool, Option<usize>) {
+    let negated = matches!(pattern.first(), Some('!') | Some('^'));
+    let start = usize::from(negated);
+
+    let end = match pattern
+        .iter()
+        .skip(start + 1)
+        .position(|character| *character == ']')
+    {
+        Some(position) => position + start + 1,
+        None => return (false, None),
+    };
+
+    let members = &pattern[start..end];
+    let mut matched = false;
+    let mut i = 0;
+
+    while i < members.len() {
+        if i + 2 < members.len() && members[i + 1] == '-' {
+            matched |= members[i] <= c && c <= members[i + 2];
+            i += 3;
+        } else {
+            matched |= members[i] == c;
+            i += 1;
+        }
+    }
+
+    (matched != negated, Some(end))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyzer::{glob, Analyzer, Pattern};
+    use crate::section::Section;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        glob(
+            &pattern.chars().collect::<Vec<char>>(),
+            &text.chars().collect::<Vec<char>>(),
+        )
+    }
+
+    fn sections(sections: &[(&str, &str)]) -> Vec<Section> {
+        sections
+            .iter()
+            .map(|(heading, content)| Section::new(heading, heading, String::from(*content)))
+            .collect()
+    }
+
+    fn parse(line: &str) -> Option<Pattern> {
+        Pattern::parse(line, &Section::new("A", "A", String::new()))
+    }
+
+    #[test]
+    fn glob_with_wildcards() {
+        assert!(matches("*.log", "debug.log"));
+        assert!(!matches("*.log", "logs/debug.log"));
+        assert!(matches("**/debug.log", "logs/debug.log"));
+        assert!(matches("**/debug.log", "debug.log"));
+        assert!(matches("logs/**", "logs/a/b.log"));
+        assert!(matches("debug?.log", "debug1.log"));
+        assert!(!matches("debug?.log", "debug.log"));
+    }
+
+    #[test]
+    fn glob_with_classes() {
+        assert!(matches("debug[0-9].log", "debug1.log"));
+        assert!(!matches("debug[!0-9].log", "debug1.log"));
+        assert!(matches("debug[ab].log", "debugb.log"));
+        assert!(matches("[abc", "[abc"));
+        assert!(matches("\\*.log", "*.log"));
+        assert!(!matches("\\*.log", "debug.log"));
+    }
+
+    #[test]
+    fn pattern_matches_at_any_level() {
+        let unanchored = parse("build/").unwrap();
+        let anchored = parse("/build").unwrap();
+
+        assert!(unanchored.matches("src/build/output.o"));
+        assert!(anchored.matches("build/output.o"));
+        assert!(!anchored.matches("src/build/output.o"));
+    }
+
+    #[test]
+    fn parse_skips_comments() {
+        assert!(parse("# Logs").is_none());
+        assert!(parse("   ").is_none());
+        assert!(parse("\\#file").is_some());
+    }
+
+    #[test]
+    fn conflict_with_negation_in_later_section() {
+        let analyzer = Analyzer::new(&sections(&[
+            ("Node.gitignore", ".env\n.env*\n*.log\n"),
+            ("Python.gitignore", "!.env.example\n"),
+        ]));
+
+        let conflicts = analyzer.conflicts();
+
+        assert_eq!(1, conflicts.len());
+        assert_eq!(
+            "'.env*' in Node.gitignore is re-included by '!.env.example' in Python.gitignore",
+            conflicts[0].to_string()
+        );
+    }
+
+    #[test]
+    fn conflict_with_broader_pattern_in_later_section() {
+        let analyzer = Analyzer::new(&sections(&[
+            ("Java.gitignore", "*.jar\n!gradle-wrapper.jar\n"),
+            ("Custom", "*.jar\n"),
+        ]));
+
+        let conflicts = analyzer.conflicts();
+
+        assert_eq!(1, conflicts.len());
+        assert_eq!("!gradle-wrapper.jar", conflicts[0].pattern());
+        assert_eq!("Custom", conflicts[0].overriding_section());
+        assert!(conflicts[0].to_string().contains("ignored again by"));
+    }
+
+    #[test]
+    fn conflict_between_sections_with_same_heading() {
+        let analyzer = Analyzer::new(&[
+            Section::new("Foo", "Foo.gitignore", String::from("*.log\n")),
+            Section::new("Global/Foo", "Foo.gitignore", String::from("!keep.log\n")),
+        ]);
+
+        let conflicts = analyzer.conflicts();
+
+        assert_eq!(1, conflicts.len());
+        assert_eq!("Foo.gitignore", conflicts[0].section());
+        assert_eq!("Foo.gitignore", conflicts[0].overriding_section());
+    }
+
+    #[test]
+    fn no_conflict_within_section_or_without_overlap() {
+        let analyzer = Analyzer::new(&sections(&[
+            ("Java.gitignore", "*.jar\n!gradle-wrapper.jar\n"),
+            ("Node.gitignore", "!.env.example\n# *.jar\n"),
+        ]));
+
+        assert!(analyzer.conflicts().is_empty());
+    }
+}
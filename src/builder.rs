@@ -1,20 +1,117 @@
-use crate::query::Query;
+use crate::header::Header;
+use crate::query::{Exclusion, Query};
 use crate::repository::{Repository, Template};
-use std::collections::hash_map::DefaultHasher;
+use crate::section::{Document, Section};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::env::temp_dir;
-use std::fs::{read_to_string, File};
-use std::hash::{Hash, Hasher};
-use std::io::{Error, Write};
-use std::path::PathBuf;
+use std::fmt::{Display, Formatter};
+use std::fs::{read_dir, read_to_string, remove_file, File};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
 
 const FILE_NAME_PREFIX: &str = "alfred-gitignore-";
 
+const FILE_NAME_SUFFIX: &str = ".gitignore";
+
+/// The age after which old builds are removed from the temporary directory.
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The name of the section with the user's custom patterns.
+pub const CUSTOM_SECTION: &str = "Custom";
+
+/// The order in which templates are written to the `.gitignore` file.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Order {
+    /// The templates are written in the order in which the user selected them.
+    #[default]
+    AsTyped,
+
+    /// The templates are sorted alphabetically by their name.
+    Alphabetical,
+
+    /// The templates are grouped by their category, and sorted alphabetically within a category.
+    Category,
+}
+
+impl Display for Order {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Order::AsTyped => "as-typed",
+            Order::Alphabetical => "alphabetical",
+            Order::Category => "category",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Order {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "as-typed" => Ok(Order::AsTyped),
+            "alphabetical" => Ok(Order::Alphabetical),
+            "category" => Ok(Order::Category),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown order '{s}', expected as-typed, alphabetical, or category"),
+            )),
+        }
+    }
+}
+
+/// The style in which templates are written to the `.gitignore` file.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Style {
+    /// The templates are written with their comments, and wrapped in markers.
+    #[default]
+    Full,
+
+    /// The templates are written without comments and blank lines, with a single heading each.
+    Minified,
+}
+
+impl Display for Style {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Style::Full => "full",
+            Style::Minified => "minified",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Style {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(Style::Full),
+            "minified" => Ok(Style::Minified),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown style '{s}', expected full or minified"),
+            )),
+        }
+    }
+}
+
 /// Constructs a `.gitignore` file from a query.
 ///
 /// The builder combines the `*.gitignore` templates in a query into a single `.gitignore` file.
 pub struct Builder {
     repository: Repository,
     templates: Vec<Template>,
+    exclusions: HashMap<String, Vec<Exclusion>>,
+    extras: Vec<String>,
+    dedupe: bool,
+    header: Option<Header>,
+    style: Style,
 }
 
 impl Builder {
@@ -23,70 +120,341 @@ impl Builder {
         Builder {
             repository,
             templates: query.sanitized_query(),
+            exclusions: query.exclusions(),
+            extras: Vec::new(),
+            dedupe: false,
+            header: None,
+            style: Style::Full,
+        }
+    }
+
+    /// Sets the order in which the templates are written to the `.gitignore` file.
+    ///
+    /// The order is applied to both the content and the name of the file, so that two builds with
+    /// the same name always have the same content.
+    pub fn order(mut self, order: Order) -> Self {
+        match order {
+            Order::AsTyped => {}
+            Order::Alphabetical => self
+                .templates
+                .sort_by(|a, b| a.comparator().cmp(b.comparator())),
+            Order::Category => self.templates.sort_by(|a, b| {
+                a.category()
+                    .cmp(&b.category())
+                    .then_with(|| a.comparator().cmp(b.comparator()))
+            }),
         }
+
+        self
+    }
+
+    /// Sets custom patterns that are appended to the `.gitignore` file.
+    ///
+    /// The patterns are written to their own section at the end of the file, after all templates.
+    pub fn extras(mut self, extras: Vec<String>) -> Self {
+        self.extras = extras;
+        self
+    }
+
+    /// Sets whether patterns that are repeated across templates are only written once.
+    pub fn dedupe(mut self, dedupe: bool) -> Self {
+        self.dedupe = dedupe;
+        self
+    }
+
+    /// Sets the header that documents how the `.gitignore` file was generated.
+    ///
+    /// With a header, every template is also annotated with its source in the upstream repository.
+    pub fn header(mut self, header: Header) -> Self {
+        self.header = Some(header);
+        self
+    }
+
+    /// Sets the style in which the templates are written to the `.gitignore` file.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
     }
 
     /// Build a single `.gitignore` file from the query.
     ///
     /// The templates selected in the query are written to a single `.gitignore` file at a temporary
-    /// location on the local disk, and the path to the file is returned.
-    pub fn build(&self) -> Result<PathBuf, Error> {
-        let destination = temp_dir().join(self.file_name());
-        let mut file = File::create(&destination)?;
+    /// location on the local disk, and the path to the file is returned. Builds that are older
+    /// than a day are removed from the temporary location.
+    pub fn build(&self, sections: &[Section]) -> Result<PathBuf, Error> {
+        let content = self.render(sections);
+        let destination = temp_dir().join(Builder::file_name(&content, FILE_NAME_SUFFIX));
+        Builder::write(&destination, &content)?;
 
-        for i in 0..self.templates.len() {
-            if let Some(template) = self.templates.get(i) {
-                let template_path = self.repository.path().join(template.file_name());
-                let content = read_to_string(template_path)?;
+        // Old builds are only a cache, and failing to remove them should not fail the build
+        let _ = Builder::clean(&temp_dir(), &destination);
 
-                file.write_all(format!("### {}\n", template.file_name()).as_bytes())?;
-                file.write_all(content.as_bytes())?;
+        Ok(destination)
+    }
 
-                if i < self.templates.len() - 1 {
-                    file.write_all(b"\n")?;
-                }
+    /// Writes the content of a `.gitignore` file to the given destination.
+    ///
+    /// An existing file at the destination is overwritten, so callers must make sure that the user
+    /// agreed to replace it.
+    fn write(destination: &Path, content: &str) -> Result<(), Error> {
+        let mut file = File::create(destination)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    }
+
+    /// Removes old builds from the given directory, except for the file that should be kept.
+    ///
+    /// Every combination of templates creates a new file, so without cleaning up, the files would
+    /// pile up in the temporary directory until the system removes them. Only files with the same
+    /// extension as the kept file are removed, so that builds and diffs are cleaned up separately.
+    /// Returns the number of files that were removed.
+    pub fn clean(directory: &Path, keep: &Path) -> Result<usize, Error> {
+        let mut removed = 0;
+
+        for entry in read_dir(directory)? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+
+            if path == keep
+                || !name.starts_with(FILE_NAME_PREFIX)
+                || path.extension() != keep.extension()
+            {
+                continue;
             }
-        }
 
-        file.sync_all()?;
+            let age = entry.metadata()?.modified()?.elapsed().unwrap_or_default();
+            if age > MAX_AGE {
+                remove_file(path)?;
+                removed += 1;
+            }
+        }
 
-        Ok(destination)
+        Ok(removed)
     }
 
-    /// Returns the file name for the given query.
+    /// Returns the content of the `.gitignore` file.
     ///
-    /// The names of the templates in the given query are concatenated and then hashed to allow the
-    /// results of the query to be cached.
-    fn file_name(&self) -> PathBuf {
-        let mut template_names: Vec<String> = self
+    /// Every section is delimited by markers, so that it can be updated later without touching
+    /// the rest of the file. Minified sections only have a heading, and are written without blank
+    /// lines in between. The header is written before the first section.
+    pub fn render(&self, sections: &[Section]) -> String {
+        let (mut blocks, separator): (Vec<String>, &str) = match self.style {
+            Style::Full => (sections.iter().map(Section::render).collect(), "\n"),
+            Style::Minified => (sections.iter().map(Section::minify).collect(), ""),
+        };
+
+        if let Some(header) = self.render_header() {
+            blocks.insert(0, header);
+        }
+
+        blocks.join(separator)
+    }
+
+    /// Returns the header of the `.gitignore` file, or `None` if the file has no header.
+    pub fn render_header(&self) -> Option<String> {
+        let templates: Vec<String> = self
             .templates
             .iter()
-            .map(|template| template.name())
-            .cloned()
+            .map(|template| template.qualified_name().clone())
             .collect();
 
-        template_names.sort();
+        self.header.as_ref().map(|header| header.render(&templates))
+    }
+
+    /// Merges the sections into the content of an existing `.gitignore` file.
+    ///
+    /// Sections that already exist in the file are updated, and new sections are appended. Lines
+    /// outside of the sections are preserved, which is why no header is written when merging.
+    pub fn merge(&self, existing: &str, sections: &[Section]) -> Result<String, Error> {
+        if self.style == Style::Minified {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Minified templates have no markers, and cannot be merged into a file",
+            ));
+        }
+
+        let mut document = Document::parse(existing)?;
+        document.merge(sections.to_vec());
+
+        Ok(document.render())
+    }
+
+    /// Returns the sections of the `.gitignore` file.
+    ///
+    /// Every template has its own section, followed by a section for the custom patterns. Repeated
+    /// patterns are commented out if the dedupe pass is enabled. Reading the templates is the
+    /// expensive part of a build, so the sections are computed once and passed to the methods that
+    /// render them, and to the [`Analyzer`] that checks them for conflicts.
+    pub fn sections(&self) -> Result<Vec<Section>, Error> {
+        let mut sections = Vec::new();
+
+        for template in &self.templates {
+            let template_path = self.repository.path().join(template.path());
+            let mut content = match self.exclusions.get(template.path()) {
+                Some(exclusions) => Builder::exclude(&read_to_string(template_path)?, exclusions),
+                None => read_to_string(template_path)?,
+            };
+
+            if let Some(header) = &self.header {
+                content.insert_str(0, &header.source(template.path()));
+            }
+
+            sections.push(Section::new(
+                template.qualified_name(),
+                template.file_name(),
+                content,
+            ));
+        }
+
+        if !self.extras.is_empty() {
+            let content = self
+                .extras
+                .iter()
+                .map(|pattern| format!("{pattern}\n"))
+                .collect();
+
+            sections.push(Section::new(CUSTOM_SECTION, CUSTOM_SECTION, content));
+        }
+
+        if self.dedupe {
+            Builder::deduplicate(&mut sections);
+        }
+
+        Ok(sections)
+    }
+
+    /// Returns the exclusions that do not match a line in the template they follow.
+    ///
+    /// An exclusion must match a pattern exactly, so `-dist` does not exclude `dist/`. Each
+    /// unmatched exclusion is returned with the file name of its template, since it would
+    /// otherwise leave the pattern in the `.gitignore` file without any notice.
+    pub fn unmatched_exclusions(&self, sections: &[Section]) -> Vec<(String, String)> {
+        let mut unmatched = Vec::new();
 
-        let mut hasher = DefaultHasher::new();
-        template_names.join("").hash(&mut hasher);
-        let hash = hasher.finish();
+        for template in &self.templates {
+            let exclusions = match self.exclusions.get(template.path()) {
+                Some(exclusions) => exclusions,
+                None => continue,
+            };
+            let excluded = sections
+                .iter()
+                .find(|section| section.name() == template.qualified_name())
+                .map(Section::exclusions)
+                .unwrap_or_default();
 
-        let mut file_name = String::from(FILE_NAME_PREFIX);
-        file_name.push_str(&hash.to_string());
-        file_name.push_str(".gitignore");
+            for exclusion in exclusions {
+                if !excluded.contains(exclusion.pattern()) {
+                    unmatched.push((template.file_name().clone(), exclusion.pattern().clone()));
+                }
+            }
+        }
 
-        PathBuf::from(file_name)
+        unmatched
+    }
+
+    /// Comments out patterns that already appear in an earlier section.
+    ///
+    /// Git uses the last pattern that matches a path, so a negated pattern like `!.env.example`
+    /// between two occurrences of `.env*` is overridden by the second occurrence. Removing it
+    /// would thus change which files are ignored. A repeated pattern is therefore only commented
+    /// out if no pattern of the opposite polarity has been written since its last occurrence.
+    fn deduplicate(sections: &mut [Section]) {
+        // Maps each pattern to the section it was last written in, and whether a pattern of the
+        // opposite polarity has been written since
+        let mut written: HashMap<String, (String, bool)> = HashMap::new();
+
+        for section in sections.iter_mut() {
+            let content = section
+                .content()
+                .split_inclusive('\n')
+                .map(|line| {
+                    let pattern = line.trim();
+
+                    if pattern.is_empty() || pattern.starts_with('#') {
+                        return String::from(line);
+                    }
+
+                    if let Some((heading, false)) = written.get(pattern) {
+                        return format!("# Already in {heading}: {line}");
+                    }
+
+                    let negated = pattern.starts_with('!');
+                    for (other, (_heading, overridden)) in written.iter_mut() {
+                        if other.starts_with('!') != negated {
+                            *overridden = true;
+                        }
+                    }
+                    written.insert(String::from(pattern), (section.heading().clone(), false));
+
+                    String::from(line)
+                })
+                .collect();
+
+            section.set_content(content);
+        }
+    }
+
+    /// Comments out the excluded patterns in a template.
+    ///
+    /// Patterns are commented out instead of being removed, so that the `.gitignore` file records
+    /// which patterns were excluded and why.
+    fn exclude(content: &str, exclusions: &[Exclusion]) -> String {
+        content
+            .split_inclusive('\n')
+            .map(|line| {
+                let pattern = line.trim();
+
+                match exclusions
+                    .iter()
+                    .find(|exclusion| exclusion.pattern() == pattern)
+                {
+                    Some(exclusion) => format!("# {}: {}", exclusion.reason(), line),
+                    None => String::from(line),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the file name for the given content, which ends with the given suffix.
+    ///
+    /// The name is derived from the SHA-256 hash of the content, which is stable across Rust
+    /// releases and platforms. Since the content contains the markers of every section, the names
+    /// of the templates are separated, and any option that changes the content also changes the
+    /// name of the file.
+    pub fn file_name(content: &str, suffix: &str) -> PathBuf {
+        let hash = Sha256::digest(content.as_bytes());
+
+        PathBuf::from(format!("{FILE_NAME_PREFIX}{hash:x}{suffix}"))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::builder::Builder;
+    use crate::analyzer::Analyzer;
+    use crate::builder::{Builder, Order, Style};
+    use crate::header::Header;
     use crate::query::Query;
+    use crate::section::Section;
     use crate::testing::initialize_repository;
-    use std::fs::read_to_string;
+    use std::fs::{read_to_string, File};
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
     use tempfile::TempDir;
 
+    /// Builds the `.gitignore` file and returns its path.
+    fn build(builder: &Builder) -> PathBuf {
+        builder.build(&builder.sections().unwrap()).unwrap()
+    }
+
+    fn sections(sections: &[(&str, &str)]) -> Vec<Section> {
+        sections
+            .iter()
+            .map(|(heading, content)| Section::new(heading, heading, String::from(*content)))
+            .collect()
+    }
+
     #[test]
     fn build_one_file() {
         let tempdir = TempDir::new().unwrap();
@@ -94,11 +462,13 @@ mod tests {
         let query = Query::new(&repository, Some(vec!["apples"])).unwrap();
 
         let builder = Builder::new(repository, &query);
-        let path = builder.build().unwrap();
+        let path = build(&builder);
 
         let mut expected = String::new();
+        expected.push_str("# >>> alfred-gitignore: apples >>>\n");
         expected.push_str("### apples.gitignore\n");
         expected.push_str(include_str!("../tests/files/repository/apples.gitignore"));
+        expected.push_str("# <<< alfred-gitignore: apples <<<\n");
 
         let content = read_to_string(path).unwrap();
 
@@ -112,14 +482,18 @@ mod tests {
         let query = Query::new(&repository, Some(vec!["oranges", "apples"])).unwrap();
 
         let builder = Builder::new(repository, &query);
-        let path = builder.build().unwrap();
+        let path = build(&builder);
 
         let mut expected = String::new();
+        expected.push_str("# >>> alfred-gitignore: oranges >>>\n");
         expected.push_str("### oranges.gitignore\n");
         expected.push_str(include_str!("../tests/files/repository/oranges.gitignore"));
+        expected.push_str("# <<< alfred-gitignore: oranges <<<\n");
 
-        expected.push_str("\n### apples.gitignore\n");
+        expected.push_str("\n# >>> alfred-gitignore: apples >>>\n");
+        expected.push_str("### apples.gitignore\n");
         expected.push_str(include_str!("../tests/files/repository/apples.gitignore"));
+        expected.push_str("# <<< alfred-gitignore: apples <<<\n");
 
         let content = read_to_string(path).unwrap();
         assert_eq!(expected, content);
@@ -132,10 +506,331 @@ mod tests {
         let query = Query::new(&repository, Some(vec!["apples"])).unwrap();
 
         let builder = Builder::new(repository, &query);
+        let path = build(&builder);
+
+        assert_eq!(
+            "alfred-gitignore-9b223247cf9b75b5146c3b8b3144fd18ff259c57436b34d0bfebc4b0358d5920.gitignore",
+            path.file_name().unwrap().to_str().unwrap()
+        );
+        assert_eq!(
+            Builder::file_name("# >>> alfred-gitignore: Rust >>>\n", ".gitignore"),
+            PathBuf::from("alfred-gitignore-400b4f1d4e5a54715fc8dbef240e465e54a28fef2311c8b8c2d246efac5f20ae.gitignore")
+        );
+    }
+
+    #[test]
+    fn file_name_separates_templates() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        for name in &["ab", "c", "a", "bc"] {
+            File::create(tempdir.path().join(format!("{name}.gitignore"))).unwrap();
+        }
+        let first = Query::new(&repository, Some(vec!["ab", "c"])).unwrap();
+        let second = Query::new(&repository, Some(vec!["a", "bc"])).unwrap();
+
+        let first = build(&Builder::new(repository.clone(), &first));
+        let second = build(&Builder::new(repository, &second));
+
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn clean_removes_old_builds() {
+        let tempdir = TempDir::new().unwrap();
+        let old = tempdir.path().join("alfred-gitignore-old.gitignore");
+        let recent = tempdir.path().join("alfred-gitignore-recent.gitignore");
+        let kept = tempdir.path().join("alfred-gitignore-kept.gitignore");
+        let other = tempdir.path().join("other.gitignore");
+        let diff = tempdir.path().join("alfred-gitignore-old.diff");
+
+        for path in &[&old, &kept, &other, &diff] {
+            File::create(path)
+                .unwrap()
+                .set_modified(SystemTime::now() - Duration::from_secs(2 * 86_400))
+                .unwrap();
+        }
+        File::create(&recent).unwrap();
+
+        assert_eq!(1, Builder::clean(tempdir.path(), &kept).unwrap());
+        assert!(!old.exists());
+        assert!(recent.exists());
+        assert!(kept.exists());
+        assert!(other.exists());
+        assert!(diff.exists());
+    }
+
+    #[test]
+    fn write_to_destination() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        let query = Query::new(&repository, Some(vec!["apples"])).unwrap();
+        let destination = tempdir.path().join(".gitignore");
+
+        let builder = Builder::new(repository, &query);
+        Builder::write(&destination, &builder.render(&builder.sections().unwrap())).unwrap();
+
+        assert!(read_to_string(destination)
+            .unwrap()
+            .contains("### apples.gitignore\n"));
+    }
+
+    #[test]
+    fn build_with_exclusions() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        let query = Query::new(&repository, Some(vec!["apples", "-/apples", "-/peaches"])).unwrap();
+
+        let builder = Builder::new(repository, &query);
+        let content = read_to_string(build(&builder)).unwrap();
+
+        assert!(content.contains(
+            "### apples.gitignore\n# This file ignores apples\n# Excluded by the query: /apples\n"
+        ));
+    }
+
+    #[test]
+    fn unmatched_exclusions() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        let query = Query::new(&repository, Some(vec!["apples", "-/apples", "-apples"])).unwrap();
+
+        let builder = Builder::new(repository, &query);
+        let unmatched = builder.unmatched_exclusions(&builder.sections().unwrap());
+
+        assert_eq!(
+            vec![(String::from("apples.gitignore"), String::from("apples"))],
+            unmatched
+        );
+    }
+
+    #[test]
+    fn build_with_extras() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        let query = Query::new(&repository, Some(vec!["apples"])).unwrap();
+
+        let builder = Builder::new(repository, &query)
+            .extras(vec![String::from(".env.local"), String::from("secrets/")]);
+        let content = read_to_string(build(&builder)).unwrap();
+
+        assert!(content.ends_with(
+            "<<<\n\n# >>> alfred-gitignore: Custom >>>\n### Custom\n.env.local\nsecrets/\n# <<< alfred-gitignore: Custom <<<\n"
+        ));
+    }
+
+    #[test]
+    fn build_with_dedupe() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        let query = Query::new(&repository, Some(vec!["apples"])).unwrap();
+
+        let builder = Builder::new(repository, &query)
+            .extras(vec![String::from("/apples"), String::from(".env")])
+            .dedupe(true);
+        let content = read_to_string(build(&builder)).unwrap();
+
+        assert!(content.contains("### Custom\n# Already in apples.gitignore: /apples\n.env\n"));
+    }
+
+    #[test]
+    fn build_with_header() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        let query = Query::new(&repository, Some(vec!["apples"])).unwrap();
+
+        let builder = Builder::new(repository, &query)
+            .header(Header::new(Some(String::from("2a4de26")), "--build apples"));
+        let content = read_to_string(build(&builder)).unwrap();
+
+        assert!(content.starts_with("# Generated by alfred-gitignore "));
+        assert!(content.contains("# Templates: apples\n# Regenerate: alfred-gitignore --build apples\n\n# >>> alfred-gitignore: apples >>>\n"));
+        assert!(content.contains("### apples.gitignore\n# Source: https://github.com/github/gitignore/blob/2a4de26/apples.gitignore\n"));
+    }
+
+    #[test]
+    fn merge_without_header() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        let query = Query::new(&repository, Some(vec!["apples"])).unwrap();
+
+        let builder = Builder::new(repository, &query).header(Header::new(None, "--build apples"));
+        let merged = builder
+            .merge("/target\n", &builder.sections().unwrap())
+            .unwrap();
+
+        assert!(merged.starts_with("/target\n\n# >>> alfred-gitignore: apples >>>\n"));
+        assert!(merged.contains("# Source: "));
+    }
+
+    #[test]
+    fn build_minified() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        let query = Query::new(&repository, Some(vec!["apples", "oranges"])).unwrap();
+
+        let builder = Builder::new(repository, &query)
+            .extras(vec![String::from("\\#notes")])
+            .style(Style::Minified);
+        let content = read_to_string(build(&builder)).unwrap();
+
+        assert_eq!(
+            "### apples.gitignore\n/apples\n### oranges.gitignore\n/oranges\n### Custom\n\\#notes\n",
+            content
+        );
+        assert!(builder
+            .merge("/target\n", &builder.sections().unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn deduplicate() {
+        let mut sections = sections(&[
+            ("A", "# Logs\n*.log\n.DS_Store\n"),
+            ("B", "*.log\n\n# Logs\n.DS_Store\n"),
+        ]);
+
+        Builder::deduplicate(&mut sections);
+
+        assert_eq!("# Logs\n*.log\n.DS_Store\n", sections[0].content());
+        assert_eq!(
+            "# Already in A: *.log\n\n# Logs\n# Already in A: .DS_Store\n",
+            sections[1].content()
+        );
+    }
+
+    #[test]
+    fn deduplicate_keeps_patterns_after_negation() {
+        let mut sections = sections(&[
+            ("A", ".env*\n*.log\n"),
+            ("B", "!.env.example\n"),
+            ("C", ".env*\n*.log\n!.env.example\n"),
+        ]);
+
+        Builder::deduplicate(&mut sections);
+
+        assert_eq!(".env*\n*.log\n", sections[0].content());
+        assert_eq!("!.env.example\n", sections[1].content());
+        assert_eq!(".env*\n*.log\n!.env.example\n", sections[2].content());
+    }
+
+    #[test]
+    fn deduplicate_negations() {
+        let mut sections = sections(&[("A", "*.log\n!keep.log\n"), ("B", "!keep.log\n")]);
+
+        Builder::deduplicate(&mut sections);
+
+        assert_eq!("# Already in A: !keep.log\n", sections[1].content());
+    }
+
+    #[test]
+    fn conflicts() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        let query = Query::new(&repository, Some(vec!["apples"])).unwrap();
+
+        let builder =
+            Builder::new(repository, &query).extras(vec![String::from("!/apples/golden")]);
+        let conflicts = Analyzer::new(&builder.sections().unwrap()).conflicts();
+
+        assert_eq!(1, conflicts.len());
+        assert_eq!("/apples", conflicts[0].pattern());
+        assert_eq!("apples.gitignore", conflicts[0].section());
+    }
+
+    #[test]
+    fn merge_into_existing_file() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        let query = Query::new(&repository, Some(vec!["apples", "oranges"])).unwrap();
+        let existing = "/target\n\n# >>> alfred-gitignore: apples >>>\n### apples.gitignore\n/old\n# <<< alfred-gitignore: apples <<<\n\n.env\n";
+
+        let builder = Builder::new(repository, &query);
+        let merged = builder
+            .merge(existing, &builder.sections().unwrap())
+            .unwrap();
+
+        assert!(merged.starts_with("/target\n\n# >>> alfred-gitignore: apples >>>\n"));
+        assert!(!merged.contains("/old"));
+        assert!(merged.contains("/apples\n# <<< alfred-gitignore: apples <<<\n\n.env\n"));
+        assert!(merged.ends_with("/oranges\n# <<< alfred-gitignore: oranges <<<\n"));
+    }
+
+    #[test]
+    fn build_in_alphabetical_order() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        let query = Query::new(&repository, Some(vec!["oranges", "apples"])).unwrap();
+
+        let builder = Builder::new(repository, &query).order(Order::Alphabetical);
+        let content = read_to_string(build(&builder)).unwrap();
+
+        assert!(content.starts_with("# >>> alfred-gitignore: apples >>>\n"));
+    }
+
+    #[test]
+    fn build_in_category_order() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        for name in &["macOS", "JetBrains", "Rust"] {
+            File::create(tempdir.path().join(format!("{name}.gitignore"))).unwrap();
+        }
+        let query = Query::new(&repository, Some(vec!["macOS", "JetBrains", "Rust"])).unwrap();
+
+        let builder = Builder::new(repository, &query).order(Order::Category);
+        let content = read_to_string(build(&builder)).unwrap();
+
+        let headings: Vec<&str> = content
+            .lines()
+            .filter(|line| line.starts_with("### "))
+            .collect();
 
         assert_eq!(
-            "alfred-gitignore-16623996710012718148.gitignore",
-            builder.file_name().to_str().unwrap()
+            vec![
+                "### Rust.gitignore",
+                "### JetBrains.gitignore",
+                "### macOS.gitignore"
+            ],
+            headings
         );
     }
+
+    #[test]
+    fn file_name_depends_on_order() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        let apples_first = Query::new(&repository, Some(vec!["apples", "oranges"])).unwrap();
+        let oranges_first = Query::new(&repository, Some(vec!["oranges", "apples"])).unwrap();
+
+        let expected = Builder::new(repository.clone(), &apples_first);
+        let as_typed = Builder::new(repository.clone(), &oranges_first).order(Order::AsTyped);
+        let alphabetical = Builder::new(repository, &oranges_first).order(Order::Alphabetical);
+
+        assert_ne!(build(&expected), build(&as_typed));
+        assert_eq!(build(&expected), build(&alphabetical));
+    }
+
+    #[test]
+    fn style_from_str() {
+        assert_eq!(Style::Full, "full".parse().unwrap());
+        assert_eq!(Style::Minified, "minified".parse().unwrap());
+        assert!("compact".parse::<Style>().is_err());
+    }
+
+    #[test]
+    fn order_from_str() {
+        assert_eq!(Order::AsTyped, "as-typed".parse().unwrap());
+        assert_eq!(Order::Alphabetical, "alphabetical".parse().unwrap());
+        assert_eq!(Order::Category, "category".parse().unwrap());
+        assert!("random".parse::<Order>().is_err());
+    }
+
+    #[test]
+    fn display_and_from_str() {
+        for order in &[Order::AsTyped, Order::Alphabetical, Order::Category] {
+            assert_eq!(*order, order.to_string().parse().unwrap());
+        }
+        for style in &[Style::Full, Style::Minified] {
+            assert_eq!(*style, style.to_string().parse().unwrap());
+        }
+    }
 }
@@ -0,0 +1,217 @@
+use std::fmt::{Display, Formatter};
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+
+/// Frameworks, engines, and build tools in `github/gitignore`.
+const FRAMEWORKS: &[&str] = &[
+    "android",
+    "angular",
+    "cakephp",
+    "cmake",
+    "codeigniter",
+    "composer",
+    "drupal",
+    "flutter",
+    "godot",
+    "gradle",
+    "grails",
+    "jekyll",
+    "joomla",
+    "laravel",
+    "magento",
+    "maven",
+    "nextjs",
+    "qt",
+    "rails",
+    "symfony",
+    "terraform",
+    "unity",
+    "unrealengine",
+    "wordpress",
+    "yii",
+    "zendframework",
+];
+
+/// Editors and IDEs in `github/gitignore`.
+const EDITORS: &[&str] = &[
+    "anjuta",
+    "bricxcc",
+    "cloud9",
+    "darteditor",
+    "dreamweaver",
+    "eclipse",
+    "eiffelstudio",
+    "emacs",
+    "ensime",
+    "espresso",
+    "flexbuilder",
+    "jdeveloper",
+    "jetbrains",
+    "kate",
+    "kdevelop4",
+    "lazarus",
+    "lyx",
+    "momentics",
+    "monodevelop",
+    "netbeans",
+    "notepadpp",
+    "psoccreator",
+    "redcar",
+    "slickedit",
+    "sublimetext",
+    "textmate",
+    "vim",
+    "visualstudio",
+    "visualstudiocode",
+    "xcode",
+    "xilinxise",
+];
+
+/// Operating systems in `github/gitignore`.
+const OPERATING_SYSTEMS: &[&str] = &["linux", "macos", "windows"];
+
+/// The category of a template.
+///
+/// Categories are ordered the way they are typically arranged in a `.gitignore` file: the patterns
+/// for the language come first, then those for frameworks and tools, and finally the patterns for
+/// the user's environment.
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Category {
+    /// A programming language.
+    Language,
+
+    /// A framework, engine, or build tool.
+    Framework,
+
+    /// An editor or IDE.
+    Editor,
+
+    /// A tool or service in the user's environment, like a version control system.
+    Tool,
+
+    /// An operating system.
+    OperatingSystem,
+}
+
+impl Category {
+    /// All categories, in the order in which they are written to a `.gitignore` file.
+    pub const ALL: [Category; 5] = [
+        Category::Language,
+        Category::Framework,
+        Category::Editor,
+        Category::Tool,
+        Category::OperatingSystem,
+    ];
+
+    /// Returns the category for the template with the given qualified name.
+    ///
+    /// The category follows from the directory of the template in `github/gitignore`: templates
+    /// in `Global` are for the user's environment, and are operating systems, editors, or other
+    /// tools like version control systems, while templates in `community` are for frameworks and
+    /// tools. The templates at the root of the repository are looked up in a list of well-known
+    /// templates instead. Templates that are not in the list are assumed to be languages, since
+    /// they make up the majority of them.
+    pub fn of(qualified_name: &str) -> Self {
+        let (directory, name) = match qualified_name.split_once('/') {
+            Some((directory, _)) => (
+                directory.to_lowercase(),
+                qualified_name.rsplit('/').next().unwrap_or_default(),
+            ),
+            None => (String::new(), qualified_name),
+        };
+        let name = name.to_lowercase();
+
+        match directory.as_str() {
+            "global" if OPERATING_SYSTEMS.contains(&name.as_str()) => Category::OperatingSystem,
+            "global" if EDITORS.contains(&name.as_str()) => Category::Editor,
+            "global" => Category::Tool,
+            "community" => Category::Framework,
+            _ => Category::from_name(&name),
+        }
+    }
+
+    /// Returns the category for the template with the given lowercase name.
+    fn from_name(name: &str) -> Self {
+        if FRAMEWORKS.contains(&name) {
+            Category::Framework
+        } else if EDITORS.contains(&name) {
+            Category::Editor
+        } else if OPERATING_SYSTEMS.contains(&name) {
+            Category::OperatingSystem
+        } else {
+            Category::Language
+        }
+    }
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Category::Language => "language",
+            Category::Framework => "framework",
+            Category::Editor => "editor",
+            Category::Tool => "tool",
+            Category::OperatingSystem => "os",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Category {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "language" => Ok(Category::Language),
+            "framework" => Ok(Category::Framework),
+            "editor" => Ok(Category::Editor),
+            "tool" => Ok(Category::Tool),
+            "os" => Ok(Category::OperatingSystem),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Unknown category '{s}', expected language, framework, editor, tool, or os"
+                ),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::category::Category;
+
+    #[test]
+    fn of_known_templates() {
+        assert_eq!(Category::Framework, Category::of("Rails"));
+        assert_eq!(Category::Editor, Category::of("JetBrains"));
+        assert_eq!(Category::OperatingSystem, Category::of("macOS"));
+    }
+
+    #[test]
+    fn of_templates_in_directories() {
+        assert_eq!(Category::OperatingSystem, Category::of("Global/macOS"));
+        assert_eq!(Category::Tool, Category::of("Global/Archives"));
+        assert_eq!(Category::Tool, Category::of("Global/Vagrant"));
+        assert_eq!(Category::Editor, Category::of("Global/JetBrains"));
+        assert_eq!(Category::Editor, Category::of("Global/Emacs"));
+        assert_eq!(
+            Category::Framework,
+            Category::of("community/JavaScript/Vue")
+        );
+        assert_eq!(Category::Framework, Category::of("community/Rust"));
+    }
+
+    #[test]
+    fn of_unknown_template() {
+        assert_eq!(Category::Language, Category::of("Rust"));
+    }
+
+    #[test]
+    fn display_and_from_str() {
+        for category in &Category::ALL {
+            assert_eq!(*category, category.to_string().parse().unwrap());
+        }
+    }
+}
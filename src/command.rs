@@ -1,3 +1,6 @@
 pub mod build;
+pub mod check;
+pub mod favorite;
+pub mod refresh;
 pub mod select;
 pub mod update;
@@ -1,13 +1,142 @@
-use crate::builder::Builder;
-use crate::exit_with_error;
-use crate::query::Query;
+use super::select::Select;
+use crate::analyzer::{Analyzer, Conflict};
+use crate::builder::{Builder, Order, Style};
+use crate::config::Config;
+use crate::diff::Diff;
+use crate::header::Header;
+use crate::icon;
+use crate::query::{quote, Query};
 use crate::repository::Repository;
+use crate::section::Section;
+use crate::{exit_with_error, exit_with_raw_error};
 use alfred::{Item, ItemBuilder, ItemType};
-use clap::Values;
-use std::fs::read_to_string;
-use std::io::stdout;
+use getset::{CopyGetters, Getters, Setters};
+use std::env::{temp_dir, var};
+use std::fs::{read_to_string, write};
+use std::io::{stdout, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
 use std::process::exit;
 
+/// The suffix of the files in the temporary directory that the diff previews are written to.
+const DIFF_FILE_SUFFIX: &str = ".diff";
+
+/// The workflow variable with the project directory of the frontmost Finder or terminal window.
+const PROJECT_DIRECTORY_VARIABLE: &str = "project_directory";
+
+/// The options that the user passed to customize the `.gitignore` file.
+///
+/// Options that are not set on the command line fall back to the user's configuration.
+#[derive(Clone, Debug, Default, Getters, CopyGetters, Setters)]
+pub struct Options<'a> {
+    /// Returns the order in which the templates are written.
+    #[getset(get_copy = "pub", set = "pub")]
+    order: Option<Order>,
+
+    /// Returns the style in which the templates are written.
+    #[getset(get_copy = "pub", set = "pub")]
+    style: Option<Style>,
+
+    /// Returns the custom patterns that are appended to the file.
+    #[getset(get = "pub", set = "pub")]
+    extras: Vec<&'a str>,
+
+    /// Returns whether patterns that are repeated across templates are only written once.
+    #[getset(get_copy = "pub", set = "pub")]
+    dedupe: bool,
+
+    /// Returns the file or directory that the `.gitignore` file is written to.
+    #[getset(get = "pub", set = "pub")]
+    output: Option<PathBuf>,
+
+    /// Returns whether an existing file at the output may be overwritten.
+    #[getset(get_copy = "pub", set = "pub")]
+    force: bool,
+
+    /// Returns whether the sections are merged into an existing file at the output.
+    #[getset(get_copy = "pub", set = "pub")]
+    merge: bool,
+
+    /// Returns whether a header and the source of each template are written.
+    #[getset(get_copy = "pub", set = "pub")]
+    header: bool,
+
+    /// Returns whether the file is printed to stdout instead of Alfred items.
+    #[getset(get_copy = "pub", set = "pub")]
+    raw: bool,
+
+    /// Returns whether the changes to the output are shown instead of writing the file.
+    #[getset(get_copy = "pub", set = "pub")]
+    diff: bool,
+}
+
+impl<'a> Options<'a> {
+    /// Returns the options with the values from the configuration for options that are not set.
+    pub fn resolve(&self, config: &Config) -> Self {
+        let mut options = self.clone();
+        options
+            .set_order(Some(self.order.unwrap_or_else(|| config.order())))
+            .set_style(Some(self.style.unwrap_or_else(|| config.style())))
+            .set_dedupe(self.dedupe || config.dedupe())
+            .set_header(self.header || config.header());
+
+        options
+    }
+
+    /// Returns the options with the values of the given arguments.
+    ///
+    /// The arguments are recorded in the header of a file, which is why a header is always written.
+    /// Options that are not in the arguments are reset, since the file was built without them.
+    pub fn with_arguments(&self, arguments: &str) -> Result<Self, Error> {
+        let mut options = self.clone();
+        options
+            .set_order(None)
+            .set_style(None)
+            .set_dedupe(false)
+            .set_header(true);
+
+        let mut tokens = arguments.split_whitespace();
+        while let Some(token) = tokens.next() {
+            match token {
+                "--order" => {
+                    options.set_order(tokens.next().map(str::parse).transpose()?);
+                }
+                "--style" => {
+                    options.set_style(tokens.next().map(str::parse).transpose()?);
+                }
+                "--dedupe" => {
+                    options.set_dedupe(true);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// Returns the arguments for the options that change the content of the file.
+    ///
+    /// The custom patterns are not included, since they are part of the query string. Each value
+    /// is quoted, so that the arguments can be passed back to the workflow.
+    pub fn arguments(&self) -> String {
+        let mut arguments = String::new();
+
+        if let Some(order) = self.order {
+            arguments.push_str(&format!(" --order {}", quote(&order.to_string())));
+        }
+        if let Some(style) = self.style {
+            arguments.push_str(&format!(" --style {}", quote(&style.to_string())));
+        }
+        if self.dedupe {
+            arguments.push_str(" --dedupe");
+        }
+        if self.header {
+            arguments.push_str(" --header");
+        }
+
+        arguments
+    }
+}
+
 pub struct Build<'a> {
     _lifetime: &'a str,
 }
@@ -21,34 +150,445 @@ impl<'a> Build<'a> {
             .into_item()
     }
 
-    pub fn perform(repository: Repository, selections: Option<Values>) -> ! {
-        let selections = selections.map(|values| values.collect());
+    /// Returns warnings for elements of the query that do not match a template.
+    ///
+    /// Each warning suggests templates with a similar name, since the element is most likely a
+    /// typo that would otherwise silently be missing from the `.gitignore` file. Exclusions that
+    /// do not follow a template are reported as well, since they do not remove any pattern.
+    pub fn warnings(query: &Query, unresolved: &[String]) -> Vec<Item<'a>> {
+        let mut warnings: Vec<Item<'a>> = unresolved
+            .iter()
+            .map(|input| {
+                let corrections = query.corrections(input);
+                let subtitle = if corrections.is_empty() {
+                    String::from("No template or preset has this name")
+                } else {
+                    format!("Did you mean {}?", corrections.join(", "))
+                };
+
+                ItemBuilder::new(format!("Unknown template '{input}'"))
+                    .subtitle(subtitle)
+                    .icon_file(icon::warning())
+                    .valid(false)
+                    .into_item()
+            })
+            .collect();
+
+        warnings.extend(query.unmatched_exclusions().iter().map(|exclusion| {
+            ItemBuilder::new(format!("Unmatched exclusion '{exclusion}'"))
+                .subtitle("Add the exclusion after the template that contains the pattern")
+                .icon_file(icon::warning())
+                .valid(false)
+                .into_item()
+        }));
+
+        warnings
+    }
+
+    /// Returns warnings for exclusions that do not match a pattern in their template.
+    pub fn exclusions(unmatched: &[(String, String)]) -> Vec<Item<'a>> {
+        unmatched
+            .iter()
+            .map(|(template, pattern)| {
+                ItemBuilder::new(format!("Unmatched exclusion '-{pattern}'"))
+                    .subtitle(format!("{template} has no pattern '{pattern}'"))
+                    .icon_file(icon::warning())
+                    .valid(false)
+                    .into_item()
+            })
+            .collect()
+    }
+
+    /// Returns warnings for patterns that override patterns of another template.
+    pub fn conflicts(conflicts: &[Conflict]) -> Vec<Item<'a>> {
+        conflicts
+            .iter()
+            .map(|conflict| {
+                ItemBuilder::new(format!(
+                    "Conflicting patterns in {} and {}",
+                    conflict.section(),
+                    conflict.overriding_section()
+                ))
+                .subtitle(conflict.to_string())
+                .icon_file(icon::warning())
+                .valid(false)
+                .into_item()
+            })
+            .collect()
+    }
+
+    /// Returns a builder for the query that is configured with the given options.
+    ///
+    /// The options must already be resolved with the user's configuration, see
+    /// [`Options::resolve`]. The header records the arguments that build the file again, which
+    /// include the selected templates, their exclusions, the custom patterns, and every option that
+    /// changes the content.
+    pub fn builder(
+        repository: &Repository,
+        query: &Query,
+        options: &Options,
+    ) -> Result<Builder, Error> {
+        let builder = Builder::new(repository.clone(), query)
+            .order(options.order().unwrap_or_default())
+            .extras(
+                options
+                    .extras()
+                    .iter()
+                    .map(|extra| String::from(*extra))
+                    .collect(),
+            )
+            .dedupe(options.dedupe())
+            .style(options.style().unwrap_or_default());
+
+        if !options.header() {
+            return Ok(builder);
+        }
+
+        // The options are resolved, so that the file is built the same with another configuration
+        let arguments = format!(
+            "--build{}{}",
+            options.arguments(),
+            Select::construct_query_string(query, options.extras())
+        );
+
+        Ok(builder.header(Header::new(repository.revision()?, &arguments)))
+    }
+
+    /// Returns the path of the `.gitignore` file for the given output.
+    ///
+    /// The output is either a directory, in which case the file is called `.gitignore`, or the
+    /// path of the file itself.
+    pub fn destination(output: &Path) -> PathBuf {
+        if output.is_dir() {
+            output.join(".gitignore")
+        } else {
+            output.to_path_buf()
+        }
+    }
+
+    /// Returns the query that builds the selection again and writes it to the output.
+    ///
+    /// The options are carried over, so that the file that is written has the same content as the
+    /// one that the user saw. The flag decides what happens to an existing file at the output.
+    fn autocomplete(options: &Options, output: &Path, flag: &str, selection: &str) -> String {
+        format!(
+            "--build{} --output {}{flag}{selection}",
+            options.arguments(),
+            quote(&output.to_string_lossy())
+        )
+    }
+
+    pub fn perform(repository: Repository, selections: Option<Vec<&str>>, options: Options) -> ! {
+        let options = match repository.config() {
+            Ok(config) => options.resolve(&config),
+            Err(error) if options.raw() => exit_with_raw_error(&error),
+            Err(error) => exit_with_error(&error),
+        };
+
+        if options.raw() {
+            Build::print(&repository, selections, &options);
+        }
+
         let query = match Query::new(&repository, selections) {
             Ok(query) => query,
             Err(error) => exit_with_error(&error),
         };
+        let builder = match Build::builder(&repository, &query, &options) {
+            Ok(builder) => builder,
+            Err(error) => exit_with_error(&error),
+        };
+        let sections = match builder.sections() {
+            Ok(sections) => sections,
+            Err(error) => exit_with_error(&error),
+        };
+        let selection = Select::construct_query_string(&query, options.extras());
 
-        let builder = Builder::new(repository, &query);
-        let path = match builder.build() {
+        let result = match options.output() {
+            Some(output) => Build::write(&builder, &sections, output, &options, &selection),
+            None => builder.build(&sections),
+        };
+        let path = match result {
             Ok(path) => path,
             Err(error) => exit_with_error(&error),
         };
+        let unmatched = builder.unmatched_exclusions(&sections);
+        let conflicts = Analyzer::new(&sections).conflicts();
         let path_str = path.to_str().unwrap();
 
-        alfred::json::write_items(
-            stdout(),
-            &[
-                ItemBuilder::new("Open .gitignore file")
-                    .arg(path_str)
-                    .type_(ItemType::File)
-                    .icon_file(path_str)
-                    .into_item(),
-                ItemBuilder::new("Copy to clipboard")
-                    .arg(read_to_string(&path).unwrap())
+        // The history is a convenience, and failing to update it should not fail the build
+        if let Ok(mut history) = repository.history() {
+            let destination = options.output().as_ref().map(|_| path.as_path());
+            history.record(selection.trim(), options.arguments().trim(), destination);
+            let _ = history.write(repository.path());
+        }
+
+        let mut items = vec![
+            ItemBuilder::new("Open .gitignore file")
+                .arg(path_str)
+                .type_(ItemType::File)
+                .icon_file(path_str)
+                .into_item(),
+            ItemBuilder::new("Copy to clipboard")
+                .arg(read_to_string(&path).unwrap())
+                .into_item(),
+        ];
+
+        if options.output().is_none() {
+            if let Ok(project) = var(PROJECT_DIRECTORY_VARIABLE) {
+                let destination = Build::destination(Path::new(&project));
+
+                items.push(
+                    ItemBuilder::new(format!("Write to {}", destination.display()))
+                        .subtitle("Save the .gitignore file in the current project")
+                        .autocomplete(Build::autocomplete(
+                            &options,
+                            Path::new(&project),
+                            "",
+                            &selection,
+                        ))
+                        .valid(false)
+                        .into_item(),
+                );
+            }
+        }
+
+        items.extend(Build::warnings(&query, &query.unresolved()));
+        items.extend(Build::exclusions(&unmatched));
+        items.extend(Build::conflicts(&conflicts));
+
+        alfred::json::write_items(stdout(), &items).unwrap();
+
+        exit(0);
+    }
+
+    /// Prints the `.gitignore` file to stdout, so that it can be piped into a file in a terminal.
+    ///
+    /// Errors are printed to stderr with a non-zero exit code. Unlike in Alfred, unknown templates
+    /// are errors, since a warning would easily be missed in a pipeline, and so is a query that
+    /// selects no template, since it would silently write an empty file. Conflicting patterns are
+    /// only printed as warnings.
+    fn print(repository: &Repository, selections: Option<Vec<&str>>, options: &Options) -> ! {
+        let query = match Query::new(repository, selections) {
+            Ok(query) => query,
+            Err(error) => exit_with_raw_error(&error),
+        };
+
+        let unresolved = query.unresolved();
+        if !unresolved.is_empty() {
+            for input in &unresolved {
+                let corrections = query.corrections(input);
+
+                if corrections.is_empty() {
+                    eprintln!("error: Unknown template '{input}'");
+                } else {
+                    eprintln!(
+                        "error: Unknown template '{input}', did you mean {}?",
+                        corrections.join(", ")
+                    );
+                }
+            }
+
+            exit(1);
+        }
+
+        if query.sanitized_query().is_empty() && options.extras().is_empty() {
+            exit_with_raw_error(&Error::new(
+                ErrorKind::InvalidInput,
+                "No templates selected, pass the names of the templates to build",
+            ));
+        }
+
+        let builder = match Build::builder(repository, &query, options) {
+            Ok(builder) => builder,
+            Err(error) => exit_with_raw_error(&error),
+        };
+        let sections = match builder.sections() {
+            Ok(sections) => sections,
+            Err(error) => exit_with_raw_error(&error),
+        };
+        let content = builder.render(&sections);
+
+        for exclusion in query.unmatched_exclusions() {
+            eprintln!("warning: Unmatched exclusion '{exclusion}' does not follow a template");
+        }
+
+        for (template, pattern) in builder.unmatched_exclusions(&sections) {
+            eprintln!(
+                "warning: Unmatched exclusion '-{pattern}', {template} has no pattern '{pattern}'"
+            );
+        }
+
+        for conflict in Analyzer::new(&sections).conflicts() {
+            eprintln!("warning: {conflict}");
+        }
+
+        if let Err(error) = stdout().lock().write_all(content.as_bytes()) {
+            exit_with_raw_error(&error);
+        }
+
+        exit(0);
+    }
+
+    /// Writes the `.gitignore` file to the given output.
+    ///
+    /// Instead of writing the file, the user is asked to confirm that an existing file should be
+    /// replaced, unless the templates are merged into it or `force` is set. When `diff` is set, the
+    /// changes to the file are shown instead.
+    fn write(
+        builder: &Builder,
+        sections: &[Section],
+        output: &Path,
+        options: &Options,
+        selection: &str,
+    ) -> Result<PathBuf, Error> {
+        let destination = Build::destination(output);
+        let existing = if destination.exists() {
+            Some(read_to_string(&destination)?)
+        } else {
+            None
+        };
+
+        let content = match &existing {
+            Some(existing) if options.merge() => builder.merge(existing, sections)?,
+            _ => builder.render(sections),
+        };
+
+        if options.diff() {
+            let existing = existing.as_deref().unwrap_or_default();
+            let diff = Diff::new(&destination, existing, &content);
+
+            Build::preview(&destination, output, options, &diff, selection);
+        }
+
+        if let Some(existing) = &existing {
+            if !options.merge() && !options.force() {
+                let overwrite = Diff::new(&destination, existing, &content);
+                // A file that cannot be merged can still be overwritten, so the error is only shown
+                let merge = builder
+                    .merge(existing, sections)
+                    .map(|merged| Diff::new(&destination, existing, &merged).text().clone())
+                    .unwrap_or_else(|error| error.to_string());
+
+                Build::confirm(
+                    &destination,
+                    output,
+                    options,
+                    overwrite.text(),
+                    &merge,
+                    selection,
+                );
+            }
+        }
+
+        write(&destination, content)?;
+
+        Ok(destination)
+    }
+
+    /// Shows the changes that a build would make to the file at the destination.
+    ///
+    /// The diff is written to a temporary file, so that it can be opened or previewed with Quick
+    /// Look, and is also shown in large type.
+    fn preview(
+        destination: &Path,
+        output: &Path,
+        options: &Options,
+        diff: &Diff,
+        selection: &str,
+    ) -> ! {
+        if diff.is_empty() {
+            let items = vec![
+                ItemBuilder::new(format!("No changes to {}", destination.display()))
+                    .subtitle("The file already contains the selected templates")
+                    .icon_file(icon::info())
+                    .autocomplete(selection.trim())
+                    .valid(false)
                     .into_item(),
-            ],
-        )
-        .unwrap();
+            ];
+
+            alfred::json::write_items(stdout(), &items).unwrap();
+
+            exit(0);
+        }
+
+        // Every diff gets its own file, so that a preview that is still open is not replaced
+        let path = temp_dir().join(Builder::file_name(diff.text(), DIFF_FILE_SUFFIX));
+        if let Err(error) = write(&path, diff.text()) {
+            exit_with_error(&error);
+        }
+        let _ = Builder::clean(&temp_dir(), &path);
+        let path_str = path.to_str().unwrap();
+
+        let flag = if options.merge() {
+            " --merge"
+        } else {
+            " --force"
+        };
+        let items = vec![
+            ItemBuilder::new(format!("Changes to {}", destination.display()))
+                .subtitle(diff.summary())
+                .arg(path_str)
+                .type_(ItemType::File)
+                .quicklook_url(path_str)
+                .text_large_type(diff.text().as_str())
+                .into_item(),
+            ItemBuilder::new(format!("Apply changes to {}", destination.display()))
+                .subtitle("Write the .gitignore file with these changes")
+                .autocomplete(Build::autocomplete(options, output, flag, selection))
+                .valid(false)
+                .into_item(),
+            ItemBuilder::new("Cancel")
+                .subtitle("Go back to the selected templates")
+                .autocomplete(selection.trim())
+                .valid(false)
+                .into_item(),
+        ];
+
+        alfred::json::write_items(stdout(), &items).unwrap();
+
+        exit(0);
+    }
+
+    /// Asks the user to confirm that an existing file should be overwritten.
+    ///
+    /// Files are never overwritten silently, since a project's `.gitignore` file often contains
+    /// patterns that the user added by hand. Merging the templates into the file keeps them. The
+    /// changes of both options can be shown in large type.
+    fn confirm(
+        destination: &Path,
+        output: &Path,
+        options: &Options,
+        overwrite: &str,
+        merge: &str,
+        selection: &str,
+    ) -> ! {
+        let items = vec![
+            ItemBuilder::new(format!("Overwrite {}", destination.display()))
+                .subtitle("The file already exists, and will be replaced")
+                .icon_file(icon::warning())
+                .text_large_type(overwrite)
+                .autocomplete(Build::autocomplete(options, output, " --force", selection))
+                .valid(false)
+                .into_item(),
+            ItemBuilder::new(format!("Merge into {}", destination.display()))
+                .subtitle("Update the templates in the file, and keep all other lines")
+                .text_large_type(merge)
+                .autocomplete(Build::autocomplete(options, output, " --merge", selection))
+                .valid(false)
+                .into_item(),
+            ItemBuilder::new(format!("Show changes to {}", destination.display()))
+                .subtitle("Preview the changes before replacing the file")
+                .autocomplete(Build::autocomplete(options, output, " --diff", selection))
+                .valid(false)
+                .into_item(),
+            ItemBuilder::new("Cancel")
+                .subtitle("Go back to the selected templates")
+                .autocomplete(selection.trim())
+                .valid(false)
+                .into_item(),
+        ];
+
+        alfred::json::write_items(stdout(), &items).unwrap();
 
         exit(0);
     }
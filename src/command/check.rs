@@ -0,0 +1,53 @@
+use super::build::{Build, Options};
+use crate::analyzer::Analyzer;
+use crate::exit_with_error;
+use crate::query::Query;
+use crate::repository::Repository;
+use alfred::ItemBuilder;
+use std::io::stdout;
+use std::process::exit;
+
+pub struct Check<'a> {
+    _lifetime: &'a str,
+}
+
+impl<'a> Check<'a> {
+    /// Checks the templates in the query for patterns that override each other.
+    ///
+    /// The check analyzes the same content that a build would write, but does not create a file.
+    pub fn perform(repository: &Repository, selections: Option<Vec<&str>>, options: Options) -> ! {
+        let query = match Query::new(repository, selections) {
+            Ok(query) => query,
+            Err(error) => exit_with_error(&error),
+        };
+        let builder = match repository
+            .config()
+            .and_then(|config| Build::builder(repository, &query, &options.resolve(&config)))
+        {
+            Ok(builder) => builder,
+            Err(error) => exit_with_error(&error),
+        };
+        let sections = match builder.sections() {
+            Ok(sections) => sections,
+            Err(error) => exit_with_error(&error),
+        };
+        let conflicts = Analyzer::new(&sections).conflicts();
+
+        let mut items = Build::warnings(&query, &query.unresolved());
+        items.extend(Build::exclusions(&builder.unmatched_exclusions(&sections)));
+        items.extend(Build::conflicts(&conflicts));
+
+        if conflicts.is_empty() {
+            items.push(
+                ItemBuilder::new("No conflicting patterns")
+                    .subtitle("The templates do not override each other's patterns")
+                    .valid(false)
+                    .into_item(),
+            );
+        }
+
+        alfred::json::write_items(stdout(), &items).unwrap();
+
+        exit(0);
+    }
+}
@@ -0,0 +1,41 @@
+use crate::exit_with_error;
+use crate::query::Query;
+use crate::repository::Repository;
+use std::io::{Error, ErrorKind};
+
+pub struct Favorite<'a> {
+    _lifetime: &'a str,
+}
+
+impl<'a> Favorite<'a> {
+    /// Marks the given template as a favorite, or removes it from the favorites.
+    ///
+    /// The template can be addressed in the same ways as in a query. Unlike the other commands,
+    /// this one does not exit, so that the suggestions can be shown again with the updated
+    /// favorites.
+    pub fn perform(repository: &Repository, input: &str) {
+        let query = match Query::new(repository, Some(vec![input])) {
+            Ok(query) => query,
+            Err(error) => exit_with_error(&error),
+        };
+
+        // Presets resolve to multiple templates, and cannot be favorites themselves
+        let templates = query.sanitized_query();
+        let template = match templates.as_slice() {
+            [template] => template,
+            _ => exit_with_error(&Error::new(
+                ErrorKind::NotFound,
+                format!("Unknown template '{input}'"),
+            )),
+        };
+
+        let result = repository.favorites().and_then(|mut favorites| {
+            favorites.toggle(template.qualified_name());
+            favorites.write(repository.path())
+        });
+
+        if let Err(error) = result {
+            exit_with_error(&error);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use super::build::{Build, Options};
+use crate::builder::CUSTOM_SECTION;
+use crate::exit_with_error;
+use crate::header::Header;
+use crate::icon;
+use crate::query::{quote, Query};
+use crate::repository::Repository;
+use crate::section::Document;
+use alfred::{ItemBuilder, ItemType};
+use std::fs::{read_to_string, write};
+use std::io::stdout;
+use std::path::Path;
+use std::process::exit;
+
+pub struct Refresh<'a> {
+    _lifetime: &'a str,
+}
+
+impl<'a> Refresh<'a> {
+    /// Rebuilds the managed sections of an existing `.gitignore` file with the current templates.
+    ///
+    /// The templates are read from the markers of the sections, and patterns that were excluded
+    /// from a template are excluded again. The changes are only written when the user confirms
+    /// them with `--force`, and otherwise summarized so that the user can review them first. The
+    /// section with custom patterns and all lines outside of the sections are kept as they are.
+    ///
+    /// A file with a header is rebuilt with the options that are recorded in it, and its header is
+    /// updated with the current revision of the templates. A file without a header is rebuilt
+    /// without the sources of the templates, since it was built without them.
+    pub fn perform(repository: &Repository, path: &Path, options: Options) -> ! {
+        let destination = Build::destination(path);
+        let content = match read_to_string(&destination) {
+            Ok(content) => content,
+            Err(error) => exit_with_error(&error),
+        };
+        let mut document = match Document::parse(&content) {
+            Ok(document) => document,
+            Err(error) => exit_with_error(&error),
+        };
+
+        let existing_header = Header::find(&content);
+        let arguments = existing_header.and_then(Header::recorded_arguments);
+        let mut options = match repository.config().and_then(|config| {
+            let options = options.resolve(&config);
+
+            match arguments {
+                Some(arguments) => options.with_arguments(arguments),
+                None => {
+                    let mut options = options;
+                    options.set_header(false);
+                    Ok(options)
+                }
+            }
+        }) {
+            Ok(options) => options,
+            Err(error) => exit_with_error(&error),
+        };
+        options.set_extras(Vec::new());
+
+        let mut selections: Vec<String> = Vec::new();
+        for section in document.sections() {
+            if section.name() == CUSTOM_SECTION {
+                continue;
+            }
+
+            selections.push(section.name().clone());
+            for pattern in section.exclusions() {
+                selections.push(format!("-{pattern}"));
+            }
+        }
+
+        let query = match Query::new(
+            repository,
+            Some(selections.iter().map(String::as_str).collect()),
+        ) {
+            Ok(query) => query,
+            Err(error) => exit_with_error(&error),
+        };
+
+        // The recorded arguments are kept, since they also contain the custom patterns
+        let builder = match (Build::builder(repository, &query, &options), arguments) {
+            (Ok(builder), Some(arguments)) => match repository.revision() {
+                Ok(revision) => builder.header(Header::new(revision, arguments)),
+                Err(error) => exit_with_error(&error),
+            },
+            (Ok(builder), None) => builder,
+            (Err(error), _) => exit_with_error(&error),
+        };
+        let updated = match builder.sections() {
+            Ok(sections) => sections,
+            Err(error) => exit_with_error(&error),
+        };
+
+        let mut items = Vec::new();
+        for section in document.sections() {
+            if let Some(update) = updated
+                .iter()
+                .find(|update| update.name() == section.name())
+            {
+                let (added, removed) = section.changes(update);
+
+                if added > 0 || removed > 0 {
+                    items.push(
+                        ItemBuilder::new(format!("Update {}", section.heading()))
+                            .subtitle(format!("Lines: {added} added, {removed} removed"))
+                            .valid(false)
+                            .into_item(),
+                    );
+                }
+            }
+        }
+        let templates = items.len();
+
+        // The first line of the header has the date of the build, which is not a change by itself
+        let header = match (existing_header, builder.render_header()) {
+            (Some(existing), Some(header))
+                if existing.split_once('\n') != header.split_once('\n') =>
+            {
+                Some((existing, header))
+            }
+            _ => None,
+        };
+        if let Some((_, header)) = &header {
+            items.push(
+                ItemBuilder::new("Update header")
+                    .subtitle(
+                        header
+                            .lines()
+                            .nth(1)
+                            .unwrap_or_default()
+                            .trim_start_matches("# "),
+                    )
+                    .valid(false)
+                    .into_item(),
+            );
+        }
+
+        let path_str = destination.to_string_lossy();
+        let mut summary = if items.is_empty() {
+            vec![ItemBuilder::new("All templates are up to date")
+                .subtitle(path_str.clone())
+                .valid(false)
+                .into_item()]
+        } else if options.force() {
+            document.merge(updated);
+
+            let mut rendered = document.render();
+            if let Some((existing, header)) = &header {
+                rendered = rendered.replacen(existing, header, 1);
+            }
+
+            if let Err(error) = write(&destination, rendered) {
+                exit_with_error(&error);
+            }
+
+            let title = if templates == 0 {
+                String::from("Updated the header")
+            } else {
+                format!("Updated {templates} templates")
+            };
+
+            vec![ItemBuilder::new(title)
+                .subtitle(path_str.clone())
+                .arg(path_str.clone())
+                .type_(ItemType::File)
+                .icon_file(path_str.clone())
+                .into_item()]
+        } else {
+            vec![
+                ItemBuilder::new(format!("Apply updates to {}", destination.display()))
+                    .subtitle("Rewrite the outdated templates, and keep all other lines")
+                    .icon_file(icon::warning())
+                    .autocomplete(format!(
+                        "--refresh {} --force",
+                        quote(&path.to_string_lossy())
+                    ))
+                    .valid(false)
+                    .into_item(),
+            ]
+        };
+
+        summary.extend(items);
+        summary.extend(Build::warnings(&query, &query.unresolved()));
+
+        alfred::json::write_items(stdout(), &summary).unwrap();
+
+        exit(0);
+    }
+}
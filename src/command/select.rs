@@ -1,61 +1,190 @@
 use super::build::Build;
 use super::update::Update;
+use crate::builder::Builder;
 use crate::exit_with_error;
-use crate::query::Query;
+use crate::icon;
+use crate::query::{quote, Kind, Query, CATEGORY_PREFIX};
 use crate::repository::Repository;
-use alfred::ItemBuilder;
-use clap::Values;
+use alfred::{Item, ItemBuilder};
 use std::io::stdout;
 use std::process::exit;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The number of recent builds that are shown before the user starts typing.
+const MAX_RECENT_BUILDS: usize = 3;
 
 pub struct Select<'a> {
     _lifetime: &'a str,
 }
 
 impl<'a> Select<'a> {
-    pub fn perform(repository: &Repository, selections: Option<Values>) -> ! {
-        let selections = selections.map(|values| values.collect());
+    pub fn perform(repository: &Repository, selections: Option<Vec<&str>>, extras: &[&str]) -> ! {
         let query = match Query::new(repository, selections) {
             Ok(query) => query,
             Err(error) => exit_with_error(&error),
         };
-        let query_string = Select::construct_query_string(&query);
+        let selection_string = Select::construct_query_string(&query, extras);
+        let query_string = match query.category() {
+            Some(category) => format!("{selection_string} {CATEGORY_PREFIX}{category}"),
+            None => selection_string.clone(),
+        };
 
-        let mut items = if query.sanitized_query().is_empty() {
-            vec![Update::item()]
+        let mut items = if query.is_empty() && extras.is_empty() {
+            Select::recent_builds(repository)
         } else {
-            vec![Build::item(&query_string)]
+            Vec::new()
         };
 
-        for suggestion in query.suggestions() {
-            let mut autocomplete = query_string.clone();
-            autocomplete.push(' ');
-            autocomplete.push_str(&suggestion);
+        if query.sanitized_query().is_empty() && extras.is_empty() {
+            items.push(Update::item());
+        } else {
+            items.push(Build::item(&query_string));
+        }
+
+        let duplicates = query.duplicates();
+        if !duplicates.is_empty() {
+            let mut names: Vec<&str> = Vec::new();
+            for template in &duplicates {
+                if !names.contains(&template.name().as_str()) {
+                    names.push(template.name());
+                }
+            }
 
             items.push(
-                ItemBuilder::new(suggestion)
-                    .autocomplete(autocomplete)
+                ItemBuilder::new("Removed duplicate templates")
+                    .subtitle(format!("Selected more than once: {}", names.join(", ")))
+                    .icon_file(icon::info())
+                    .autocomplete(query_string.clone())
                     .valid(false)
                     .into_item(),
             );
         }
 
+        // Alfred cannot autocomplete from a modifier, so the template that was added last has its
+        // own item that adds it to the favorites or removes it, and shows the suggestions again
+        if !query.is_incomplete() {
+            if let Some(template) = query.sanitized_query().last() {
+                let title = if query.is_favorite(template) {
+                    format!("Remove {} from favorites", template.name())
+                } else {
+                    format!("Add {} to favorites", template.name())
+                };
+
+                items.push(
+                    ItemBuilder::new(title)
+                        .subtitle("Favorites are always listed first, and marked with a star")
+                        .icon_file(icon::favorite())
+                        .autocomplete(format!(
+                            "--favorite {}{query_string}",
+                            query.address(template)
+                        ))
+                        .valid(false)
+                        .into_item(),
+                );
+            }
+        }
+
+        let mut unresolved = query.unresolved();
+        if query.is_incomplete() {
+            unresolved.pop();
+        }
+        items.extend(Build::warnings(&query, &unresolved));
+
+        // The templates are only read when an exclusion could fail to match one of them
+        if !query.exclusions().is_empty() {
+            let builder = Builder::new(repository.clone(), &query);
+            if let Ok(sections) = builder.sections() {
+                items.extend(Build::exclusions(&builder.unmatched_exclusions(&sections)));
+            }
+        }
+
+        for suggestion in query.suggestions() {
+            // A qualifier replaces the one in the query instead of being added to it
+            let mut autocomplete = match suggestion.kind() {
+                Kind::Category => selection_string.clone(),
+                _ => query_string.clone(),
+            };
+            autocomplete.push(' ');
+            autocomplete.push_str(suggestion.address());
+
+            let mut item = ItemBuilder::new(suggestion.name().clone())
+                .autocomplete(autocomplete)
+                .valid(false);
+
+            if let Some(description) = suggestion.description() {
+                item = item.subtitle(description.clone());
+            }
+
+            if suggestion.favorite() {
+                item = item.icon_file(icon::favorite());
+            }
+
+            if suggestion.kind() == Kind::Preset {
+                item = item.icon_file(icon::preset());
+            }
+
+            items.push(item.into_item());
+        }
+
         alfred::json::write_items(stdout(), &items).unwrap();
 
         exit(0);
     }
 
-    fn construct_query_string(query: &Query) -> String {
+    /// Returns items that repeat the most recent builds.
+    ///
+    /// A history that cannot be read is ignored, since the workflow is fully usable without it.
+    fn recent_builds(repository: &Repository) -> Vec<Item<'a>> {
+        let history = match repository.history() {
+            Ok(history) => history,
+            Err(_) => return Vec::new(),
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        history
+            .entries()
+            .iter()
+            .take(MAX_RECENT_BUILDS)
+            .map(|entry| {
+                let destination = match entry.destination() {
+                    Some(destination) => destination.display().to_string(),
+                    None => String::from("a temporary file"),
+                };
+
+                ItemBuilder::new(entry.query().clone())
+                    .subtitle(format!("Built {} to {destination}", entry.age(now)))
+                    .autocomplete(entry.arguments())
+                    .valid(false)
+                    .into_item()
+            })
+            .collect()
+    }
+
+    /// Returns the query string that reproduces the current selection.
+    ///
+    /// Custom patterns are passed as options, which have to precede the templates.
+    pub fn construct_query_string(query: &Query, extras: &[&str]) -> String {
         let mut query_string = String::new();
         let sanitized_query = query.sanitized_query();
 
+        for extra in extras {
+            query_string.push_str(&format!(" --extra {}", quote(extra)));
+        }
+
         if !sanitized_query.is_empty() {
-            let names: Vec<String> = query
-                .sanitized_query()
-                .iter()
-                .map(|template| template.name())
-                .cloned()
-                .collect();
+            let exclusions = query.exclusions();
+            let mut names: Vec<String> = Vec::new();
+
+            for template in &sanitized_query {
+                names.push(query.address(template));
+
+                for exclusion in exclusions.get(template.path()).into_iter().flatten() {
+                    names.push(format!("-{}", exclusion.pattern()));
+                }
+            }
 
             query_string.push(' ');
             query_string.push_str(&names.join(" "));
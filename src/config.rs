@@ -0,0 +1,250 @@
+use crate::builder::{Order, Style};
+use getset::{CopyGetters, Getters};
+use std::fs::read_to_string;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+/// The name of the configuration file in the workflow data directory.
+const CONFIG_FILE: &str = "config";
+
+/// The key for the order in which templates are written.
+const ORDER_KEY: &str = "order";
+
+/// The key for removing patterns that are repeated across templates.
+const DEDUPE_KEY: &str = "dedupe";
+
+/// The key for the style in which templates are written.
+const STYLE_KEY: &str = "style";
+
+/// The key for writing a header and the source of each template.
+const HEADER_KEY: &str = "header";
+
+/// The prefix for keys that define a preset.
+const PRESET_PREFIX: &str = "preset.";
+
+/// A preset is a named list of templates.
+///
+/// Presets are defined by the user in the configuration file, and make it possible to add a
+/// combination of templates that is used frequently with a single name.
+#[derive(Clone, Debug, Getters)]
+pub struct Preset {
+    /// Returns the name of the preset.
+    #[getset(get = "pub")]
+    name: String,
+
+    /// Returns a sanitized version of the preset's name for comparisons.
+    #[getset(get = "pub")]
+    comparator: String,
+
+    /// Returns the names of the templates in the preset.
+    #[getset(get = "pub")]
+    templates: Vec<String>,
+}
+
+impl Preset {
+    /// Returns a new preset with the given name and templates.
+    pub fn new(name: &str, templates: Vec<String>) -> Preset {
+        Preset {
+            name: String::from(name),
+            comparator: name.to_lowercase(),
+            templates,
+        }
+    }
+}
+
+/// The user's configuration for the workflow.
+///
+/// The configuration is stored in a plain text file called `config` in the workflow data
+/// directory. Each line contains a key and a value that are separated by an equal sign, and lines
+/// that start with a `#` are ignored. Presets are defined by prefixing their name with `preset.`,
+/// and listing the templates separated by whitespace:
+///
+/// ```text
+/// # Templates for Rust projects
+/// preset.rust-stack = Rust JetBrains macOS VisualStudioCode
+/// ```
+///
+/// The order in which templates are written to the `.gitignore` file can be set with the `order`
+/// key, and is either `as-typed`, `alphabetical`, or `category`. Patterns that are repeated across
+/// templates are only written once when `dedupe` is set to `true`, and a header that documents how
+/// the file was generated is written when `header` is set to `true`. Comments and blank lines are
+/// removed from the templates when `style` is set to `minified`.
+#[derive(Clone, Debug, Default, Getters, CopyGetters)]
+pub struct Config {
+    /// Returns the order in which templates are written.
+    #[getset(get_copy = "pub")]
+    order: Order,
+
+    /// Returns whether patterns that are repeated across templates are only written once.
+    #[getset(get_copy = "pub")]
+    dedupe: bool,
+
+    /// Returns whether a header and the source of each template are written.
+    #[getset(get_copy = "pub")]
+    header: bool,
+
+    /// Returns the style in which templates are written.
+    #[getset(get_copy = "pub")]
+    style: Style,
+
+    /// Returns the presets that the user has defined.
+    #[getset(get = "pub")]
+    presets: Vec<Preset>,
+}
+
+impl Config {
+    /// Loads the configuration from the given directory.
+    ///
+    /// A missing configuration file is not an error, since most users will never create one. In
+    /// this case, the default configuration is returned.
+    pub fn load(directory: &Path) -> Result<Self, Error> {
+        let path = directory.join(CONFIG_FILE);
+
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        Config::parse(&read_to_string(path)?)
+    }
+
+    /// Parses the content of a configuration file.
+    fn parse(content: &str) -> Result<Self, Error> {
+        let mut config = Config::default();
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "Line {} of the configuration is not a key-value pair",
+                            index + 1
+                        ),
+                    ))
+                }
+            };
+
+            let invalid = |error: &dyn std::fmt::Display| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Line {} of the configuration is invalid: {}",
+                        index + 1,
+                        error
+                    ),
+                )
+            };
+
+            if key == ORDER_KEY {
+                config.order = value.parse().map_err(|error| invalid(&error))?;
+            } else if key == DEDUPE_KEY {
+                config.dedupe = value.parse().map_err(|error| invalid(&error))?;
+            } else if key == STYLE_KEY {
+                config.style = value.parse().map_err(|error| invalid(&error))?;
+            } else if key == HEADER_KEY {
+                config.header = value.parse().map_err(|error| invalid(&error))?;
+            } else if let Some(name) = key.strip_prefix(PRESET_PREFIX) {
+                let templates = value.split_whitespace().map(String::from).collect();
+                config.presets.push(Preset::new(name, templates));
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::{Order, Style};
+    use crate::config::Config;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn load_without_file() {
+        let tempdir = TempDir::new().unwrap();
+
+        let config = Config::load(tempdir.path()).unwrap();
+
+        assert!(config.presets().is_empty());
+    }
+
+    #[test]
+    fn load_with_file() {
+        let tempdir = TempDir::new().unwrap();
+        let mut file = File::create(tempdir.path().join("config")).unwrap();
+        file.write_all(b"preset.fruits = apples oranges\n").unwrap();
+        file.sync_all().unwrap();
+
+        let config = Config::load(tempdir.path()).unwrap();
+
+        assert_eq!(1, config.presets().len());
+    }
+
+    #[test]
+    fn parse_presets() {
+        let config = Config::parse("# Comment\n\npreset.Fruits = apples  oranges\n").unwrap();
+        let preset = config.presets().first().unwrap();
+
+        assert_eq!("Fruits", preset.name());
+        assert_eq!("fruits", preset.comparator());
+        assert_eq!(&vec!["apples", "oranges"], preset.templates());
+    }
+
+    #[test]
+    fn parse_invalid_line() {
+        let error = Config::parse("preset.fruits apples").unwrap_err();
+
+        assert_eq!(
+            "Line 1 of the configuration is not a key-value pair",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn parse_order() {
+        let config = Config::parse("order = category\n").unwrap();
+
+        assert_eq!(Order::Category, config.order());
+    }
+
+    #[test]
+    fn parse_invalid_order() {
+        let error = Config::parse("order = random").unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("Line 1 of the configuration is invalid"));
+    }
+
+    #[test]
+    fn parse_dedupe() {
+        assert!(Config::parse("dedupe = true\n").unwrap().dedupe());
+        assert!(!Config::parse("").unwrap().dedupe());
+        assert!(Config::parse("dedupe = yes\n").is_err());
+    }
+
+    #[test]
+    fn parse_header() {
+        assert!(Config::parse("header = true\n").unwrap().header());
+        assert!(!Config::parse("").unwrap().header());
+    }
+
+    #[test]
+    fn parse_style() {
+        assert_eq!(
+            Style::Minified,
+            Config::parse("style = minified\n").unwrap().style()
+        );
+        assert_eq!(Style::Full, Config::parse("").unwrap().style());
+        assert!(Config::parse("style = compact\n").is_err());
+    }
+}
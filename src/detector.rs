@@ -0,0 +1,125 @@
+use std::fs::read_dir;
+use std::io::Error;
+use std::path::PathBuf;
+
+/// A marker is a file or directory that indicates which templates a project needs.
+enum Marker {
+    /// A file or directory with exactly this name.
+    Name(&'static str),
+
+    /// A file or directory with this extension.
+    Extension(&'static str),
+}
+
+/// The markers and the templates that they indicate.
+const MARKERS: &[(Marker, &str)] = &[
+    (Marker::Name("Cargo.toml"), "Rust"),
+    (Marker::Name("package.json"), "Node"),
+    (Marker::Name("go.mod"), "Go"),
+    (Marker::Name("Pipfile"), "Python"),
+    (Marker::Name("pyproject.toml"), "Python"),
+    (Marker::Name("requirements.txt"), "Python"),
+    (Marker::Name("setup.py"), "Python"),
+    (Marker::Name("Gemfile"), "Ruby"),
+    (Marker::Name("composer.json"), "Composer"),
+    (Marker::Name("pom.xml"), "Maven"),
+    (Marker::Name("build.gradle"), "Gradle"),
+    (Marker::Name("build.gradle.kts"), "Gradle"),
+    (Marker::Name("mix.exs"), "Elixir"),
+    (Marker::Name("pubspec.yaml"), "Dart"),
+    (Marker::Name("Package.swift"), "Swift"),
+    (Marker::Name("CMakeLists.txt"), "CMake"),
+    (Marker::Name("stack.yaml"), "Haskell"),
+    (Marker::Name(".idea"), "JetBrains"),
+    (Marker::Name(".vscode"), "VisualStudioCode"),
+    (Marker::Extension("cabal"), "Haskell"),
+    (Marker::Extension("csproj"), "VisualStudio"),
+    (Marker::Extension("sln"), "VisualStudio"),
+    (Marker::Extension("tf"), "Terraform"),
+    (Marker::Extension("xcodeproj"), "Xcode"),
+];
+
+/// Detects the templates that a project needs.
+///
+/// The detector looks at the files and directories at the root of a project, and matches them
+/// against a list of well-known markers. For example, a project with a `Cargo.toml` file is most
+/// likely written in Rust, and a project with an `.idea` directory is opened with a JetBrains IDE.
+pub struct Detector {
+    path: PathBuf,
+}
+
+impl Detector {
+    /// Returns a new detector for the project at the given location.
+    pub fn new(path: PathBuf) -> Self {
+        Detector { path }
+    }
+
+    /// Returns the names of the templates that match the project.
+    ///
+    /// The templates are returned in the order of the markers, and each template is only returned
+    /// once even if multiple of its markers were found.
+    pub fn templates(&self) -> Result<Vec<String>, Error> {
+        let file_names: Vec<PathBuf> = read_dir(&self.path)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| PathBuf::from(entry.file_name()))
+            .collect();
+
+        let mut templates: Vec<String> = Vec::new();
+
+        for (marker, template) in MARKERS {
+            let found = file_names.iter().any(|file_name| match marker {
+                Marker::Name(name) => file_name.as_os_str() == *name,
+                Marker::Extension(extension) => {
+                    file_name.extension().is_some_and(|ext| ext == *extension)
+                }
+            });
+
+            if found && !templates.iter().any(|name| name == template) {
+                templates.push(String::from(*template));
+            }
+        }
+
+        Ok(templates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::detector::Detector;
+    use std::fs::{create_dir, File};
+    use tempfile::TempDir;
+
+    #[test]
+    fn templates_without_markers() {
+        let tempdir = TempDir::new().unwrap();
+
+        let detector = Detector::new(tempdir.path().to_path_buf());
+
+        assert!(detector.templates().unwrap().is_empty());
+    }
+
+    #[test]
+    fn templates_with_markers() {
+        let tempdir = TempDir::new().unwrap();
+        File::create(tempdir.path().join("Cargo.toml")).unwrap();
+        File::create(tempdir.path().join("App.csproj")).unwrap();
+        File::create(tempdir.path().join("App.sln")).unwrap();
+        create_dir(tempdir.path().join(".idea")).unwrap();
+
+        let detector = Detector::new(tempdir.path().to_path_buf());
+
+        assert_eq!(
+            vec!["Rust", "JetBrains", "VisualStudio"],
+            detector.templates().unwrap()
+        );
+    }
+
+    #[test]
+    fn templates_with_missing_directory() {
+        let tempdir = TempDir::new().unwrap();
+
+        let detector = Detector::new(tempdir.path().join("does-not-exist"));
+
+        assert!(detector.templates().is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use getset::{CopyGetters, Getters};
+use similar::{ChangeTag, TextDiff};
+use std::path::Path;
+
+/// The number of unchanged lines that are shown around each change.
+const CONTEXT_LINES: usize = 3;
+
+/// The changes between an existing `.gitignore` file and the content that would replace it.
+///
+/// The diff is shown to the user before a file is overwritten or merged, so that patterns that
+/// were added by hand or changed upstream do not disappear unnoticed.
+#[derive(Clone, Debug, Eq, PartialEq, Getters, CopyGetters)]
+pub struct Diff {
+    /// Returns the changes in the unified diff format.
+    #[getset(get = "pub")]
+    text: String,
+
+    /// Returns the number of lines that are added.
+    #[getset(get_copy = "pub")]
+    added: usize,
+
+    /// Returns the number of lines that are removed.
+    #[getset(get_copy = "pub")]
+    removed: usize,
+}
+
+impl Diff {
+    /// Compares the existing content of the file at the given path with the proposed content.
+    pub fn new(path: &Path, existing: &str, proposed: &str) -> Self {
+        let diff = TextDiff::from_lines(existing, proposed);
+
+        let mut added = 0;
+        let mut removed = 0;
+        for change in diff.iter_all_changes() {
+            match change.tag() {
+                ChangeTag::Insert => added += 1,
+                ChangeTag::Delete => removed += 1,
+                ChangeTag::Equal => {}
+            }
+        }
+
+        let name = path.display().to_string();
+        let text = diff
+            .unified_diff()
+            .context_radius(CONTEXT_LINES)
+            .header(&name, &name)
+            .to_string();
+
+        Diff {
+            text,
+            added,
+            removed,
+        }
+    }
+
+    /// Returns whether the proposed content is the same as the existing content.
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.removed == 0
+    }
+
+    /// Returns a short summary of the changes.
+    pub fn summary(&self) -> String {
+        format!("Lines: {} added, {} removed", self.added, self.removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::Diff;
+    use std::path::Path;
+
+    #[test]
+    fn diff_with_changes() {
+        let diff = Diff::new(
+            Path::new(".gitignore"),
+            "/target\n.env\n",
+            "/target\n*.log\n",
+        );
+
+        assert_eq!(1, diff.added());
+        assert_eq!(1, diff.removed());
+        assert_eq!("Lines: 1 added, 1 removed", diff.summary());
+        assert_eq!(
+            "--- .gitignore\n+++ .gitignore\n@@ -1,2 +1,2 @@\n /target\n-.env\n+*.log\n",
+            diff.text()
+        );
+    }
+
+    #[test]
+    fn diff_without_changes() {
+        let diff = Diff::new(Path::new(".gitignore"), "/target\n", "/target\n");
+
+        assert!(diff.is_empty());
+        assert!(diff.text().is_empty());
+    }
+}
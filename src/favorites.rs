@@ -0,0 +1,109 @@
+use getset::Getters;
+use std::fs::{read_to_string, File};
+use std::io::{Error, Write};
+use std::path::Path;
+
+/// The name of the favorites file in the workflow data directory.
+const FAVORITES_FILE: &str = "favorites";
+
+/// The templates that the user has marked as favorites.
+///
+/// Favorites are listed before all other suggestions, so that the templates that a user needs
+/// most often are always at hand. They are stored in a plain text file called `favorites` in the
+/// workflow data directory, with the qualified name of one template per line.
+#[derive(Clone, Debug, Default, Getters)]
+pub struct Favorites {
+    /// Returns the qualified names of the favorite templates.
+    #[getset(get = "pub")]
+    templates: Vec<String>,
+}
+
+impl Favorites {
+    /// Loads the favorites from the given directory.
+    ///
+    /// A missing favorites file is not an error, since it is only created when the user marks
+    /// their first favorite.
+    pub fn load(directory: &Path) -> Result<Self, Error> {
+        let path = directory.join(FAVORITES_FILE);
+
+        if !path.exists() {
+            return Ok(Favorites::default());
+        }
+
+        let templates = read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect();
+
+        Ok(Favorites { templates })
+    }
+
+    /// Writes the favorites to the given directory.
+    pub fn write(&self, directory: &Path) -> Result<(), Error> {
+        let mut file = File::create(directory.join(FAVORITES_FILE))?;
+
+        let mut content = String::new();
+        for template in &self.templates {
+            content.push_str(template);
+            content.push('\n');
+        }
+
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    }
+
+    /// Returns whether the template with the given qualified name is a favorite.
+    pub fn contains(&self, qualified_name: &str) -> bool {
+        self.templates
+            .iter()
+            .any(|template| template == qualified_name)
+    }
+
+    /// Marks a template as a favorite, or removes it from the favorites if it already is one.
+    ///
+    /// Returns whether the template is a favorite afterwards.
+    pub fn toggle(&mut self, qualified_name: &str) -> bool {
+        if self.contains(qualified_name) {
+            self.templates.retain(|template| template != qualified_name);
+            false
+        } else {
+            self.templates.push(String::from(qualified_name));
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::favorites::Favorites;
+    use tempfile::TempDir;
+
+    #[test]
+    fn load_without_favorites() {
+        let tempdir = TempDir::new().unwrap();
+
+        assert!(Favorites::load(tempdir.path())
+            .unwrap()
+            .templates()
+            .is_empty());
+    }
+
+    #[test]
+    fn toggle_and_load() {
+        let tempdir = TempDir::new().unwrap();
+
+        let mut favorites = Favorites::default();
+        assert!(favorites.toggle("Global/macOS"));
+        assert!(favorites.toggle("Rust"));
+        assert!(!favorites.toggle("Global/macOS"));
+        favorites.write(tempdir.path()).unwrap();
+
+        let favorites = Favorites::load(tempdir.path()).unwrap();
+
+        assert_eq!(&vec![String::from("Rust")], favorites.templates());
+        assert!(favorites.contains("Rust"));
+        assert!(!favorites.contains("rust"));
+    }
+}
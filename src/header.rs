@@ -0,0 +1,224 @@
+use getset::{CopyGetters, Getters};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The repository that the templates are downloaded from.
+const UPSTREAM: &str = "github/gitignore";
+
+/// The URL at which the files in the upstream repository can be viewed.
+const UPSTREAM_URL: &str = "https://github.com/github/gitignore/blob";
+
+/// The branch of the upstream repository that is used when the revision is unknown.
+const UPSTREAM_BRANCH: &str = "main";
+
+/// The beginning of the first line of a header.
+const GENERATED_PREFIX: &str = "# Generated by alfred-gitignore ";
+
+/// The beginning of the last line of a header, which is followed by the arguments.
+const REGENERATE_PREFIX: &str = "# Regenerate: alfred-gitignore ";
+
+/// A header that documents how a `.gitignore` file was generated.
+///
+/// The header lists the version of the workflow, the date of the build, the revision of the
+/// templates, the selected templates, and the command that generates the file again. Together with
+/// the source of each template, this makes the file self-documenting:
+///
+/// ```text
+/// # Generated by alfred-gitignore 2.1.1 on 2024-05-01
+/// # Upstream: github/gitignore@2a4de265d37eca626309d8e115218d18985b5435
+/// # Templates: Rust, Global/macOS
+/// # Regenerate: alfred-gitignore --build --order as-typed --style full --header Rust Global/macOS
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Getters, CopyGetters)]
+pub struct Header {
+    /// Returns the commit of the upstream repository that the templates were downloaded from.
+    #[getset(get = "pub")]
+    revision: Option<String>,
+
+    /// Returns the arguments that build the same file again.
+    #[getset(get = "pub")]
+    arguments: String,
+
+    /// Returns the time of the build in seconds since the Unix epoch.
+    #[getset(get_copy = "pub")]
+    timestamp: u64,
+}
+
+impl Header {
+    /// Returns a new header for a build at the current time.
+    pub fn new(revision: Option<String>, arguments: &str) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        Header {
+            revision,
+            arguments: String::from(arguments.trim()),
+            timestamp,
+        }
+    }
+
+    /// Returns the header for a file with the given templates.
+    pub fn render(&self, templates: &[String]) -> String {
+        let upstream = match &self.revision {
+            Some(revision) => format!("{UPSTREAM}@{revision}"),
+            None => format!("{UPSTREAM} (unknown revision)"),
+        };
+
+        format!(
+            "{GENERATED_PREFIX}{} on {}\n# Upstream: {}\n# Templates: {}\n{REGENERATE_PREFIX}{}\n",
+            env!("CARGO_PKG_VERSION"),
+            date(self.timestamp),
+            upstream,
+            templates.join(", "),
+            self.arguments
+        )
+    }
+
+    /// Returns the header in the content of an existing `.gitignore` file.
+    ///
+    /// The header spans the lines from the version of the workflow to the arguments that build
+    /// the file again, which are all written by [`Header::render`].
+    pub fn find(content: &str) -> Option<&str> {
+        let mut start = None;
+        let mut offset = 0;
+
+        for line in content.split_inclusive('\n') {
+            if start.is_none() && line.starts_with(GENERATED_PREFIX) {
+                start = Some(offset);
+            }
+            offset += line.len();
+
+            if let Some(start) = start {
+                if line.starts_with(REGENERATE_PREFIX) {
+                    return Some(&content[start..offset]);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the arguments that are recorded in the given header.
+    pub fn recorded_arguments(header: &str) -> Option<&str> {
+        header
+            .lines()
+            .find_map(|line| line.strip_prefix(REGENERATE_PREFIX))
+            .map(str::trim)
+    }
+
+    /// Returns a comment with the source of the template at the given path.
+    ///
+    /// The source links to the template at the upstream revision, or to the main branch if the
+    /// revision is unknown. Each segment of the path is percent-encoded, so that the link does not
+    /// end at a space in the name of a template.
+    pub fn source(&self, path: &str) -> String {
+        let revision = self.revision.as_deref().unwrap_or(UPSTREAM_BRANCH);
+        let path: Vec<String> = path.split('/').map(encode).collect();
+
+        format!("# Source: {UPSTREAM_URL}/{revision}/{}\n", path.join("/"))
+    }
+}
+
+/// Percent-encodes a segment of a URL path.
+///
+/// Only the unreserved characters of RFC 3986 are kept, every other byte of the UTF-8 encoding is
+/// written as `%XX`.
+fn encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                String::from(byte as char)
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// Returns the date of the timestamp in UTC in the format `YYYY-MM-DD`.
+///
+/// The days since the Unix epoch are converted to a date in the proleptic Gregorian calendar,
+/// using the algorithm by Howard Hinnant that counts in eras of 400 years.
+fn date(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::header::{date, Header};
+
+    #[test]
+    fn date_from_timestamp() {
+        assert_eq!("1970-01-01", date(0));
+        assert_eq!("2000-02-29", date(951_782_400));
+        assert_eq!("2024-12-31", date(1_735_689_599));
+    }
+
+    #[test]
+    fn render_header() {
+        let header = Header {
+            revision: Some(String::from("2a4de26")),
+            arguments: String::from("--build Rust Global/macOS"),
+            timestamp: 0,
+        };
+
+        assert_eq!(
+            format!(
+                "# Generated by alfred-gitignore {} on 1970-01-01\n# Upstream: github/gitignore@2a4de26\n# Templates: Rust, Global/macOS\n# Regenerate: alfred-gitignore --build Rust Global/macOS\n",
+                env!("CARGO_PKG_VERSION")
+            ),
+            header.render(&[String::from("Rust"), String::from("Global/macOS")])
+        );
+    }
+
+    #[test]
+    fn find_header() {
+        let header = "# Generated by alfred-gitignore 2.1.1 on 2024-05-01\n# Upstream: github/gitignore@2a4de26\n# Templates: Rust\n# Regenerate: alfred-gitignore --build --header Rust\n";
+        let content = format!("/target\n{header}\n# >>> alfred-gitignore: Rust >>>\n");
+
+        assert_eq!(Some(header), Header::find(&content));
+        assert_eq!(
+            Some("--build --header Rust"),
+            Header::recorded_arguments(header)
+        );
+        assert_eq!(None, Header::find("/target\n"));
+    }
+
+    #[test]
+    fn source_without_revision() {
+        let header = Header::new(None, " --build Rust");
+
+        assert_eq!("--build Rust", header.arguments());
+        assert_eq!(
+            "# Source: https://github.com/github/gitignore/blob/main/Rust.gitignore\n",
+            header.source("Rust.gitignore")
+        );
+    }
+
+    #[test]
+    fn source_with_space() {
+        let header = Header::new(Some(String::from("abc123")), "--build Rust");
+
+        assert_eq!(
+            "# Source: https://github.com/github/gitignore/blob/abc123/Global/Visual%20Studio%2B%2B.gitignore\n",
+            header.source("Global/Visual Studio++.gitignore")
+        );
+    }
+}
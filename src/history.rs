@@ -0,0 +1,293 @@
+use crate::query::quote;
+use getset::{CopyGetters, Getters};
+use std::fs::{read_to_string, File};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The name of the history file in the workflow data directory.
+const HISTORY_FILE: &str = "history";
+
+/// The maximum number of builds that are kept in the history.
+const MAX_ENTRIES: usize = 50;
+
+/// An entry in the history describes a single build.
+#[derive(Clone, Debug, Eq, PartialEq, Getters, CopyGetters)]
+pub struct Entry {
+    /// Returns the query that selected the templates of the build.
+    #[getset(get = "pub")]
+    query: String,
+
+    /// Returns the options of the build that change the content of the file.
+    #[getset(get = "pub")]
+    options: String,
+
+    /// Returns the time of the build in seconds since the Unix epoch.
+    #[getset(get_copy = "pub")]
+    timestamp: u64,
+
+    /// Returns the path of the `.gitignore` file that the build wrote, or `None` if the build
+    /// created a temporary file.
+    #[getset(get = "pub")]
+    destination: Option<PathBuf>,
+}
+
+impl Entry {
+    /// Returns the arguments that repeat the build with the same options and output.
+    ///
+    /// Temporary files are removed after a while, so a temporary build is repeated into a new
+    /// temporary file instead of the path that it was written to.
+    pub fn arguments(&self) -> String {
+        let mut arguments = String::from("--build");
+
+        if !self.options.is_empty() {
+            arguments.push(' ');
+            arguments.push_str(&self.options);
+        }
+        if let Some(destination) = &self.destination {
+            arguments.push_str(&format!(
+                " --output {}",
+                quote(&destination.to_string_lossy())
+            ));
+        }
+        arguments.push(' ');
+        arguments.push_str(&self.query);
+
+        arguments
+    }
+
+    /// Returns a human-readable description of how long ago the build happened.
+    pub fn age(&self, now: u64) -> String {
+        let seconds = now.saturating_sub(self.timestamp);
+
+        let (amount, unit) = match seconds {
+            0..=59 => return String::from("just now"),
+            60..=3_599 => (seconds / 60, "minute"),
+            3_600..=86_399 => (seconds / 3_600, "hour"),
+            _ => (seconds / 86_400, "day"),
+        };
+
+        if amount == 1 {
+            format!("1 {unit} ago")
+        } else {
+            format!("{amount} {unit}s ago")
+        }
+    }
+}
+
+/// The history of past builds.
+///
+/// Users often create `.gitignore` files for the same combination of templates, for example for
+/// every new project in their favorite language. The history remembers the queries of past builds
+/// together with their options, so that they can be repeated without typing them again. It is
+/// stored in a plain text file called `history` in the workflow data directory, with the most
+/// recent build first.
+#[derive(Debug, Default, Getters)]
+pub struct History {
+    /// Returns the entries in the history, starting with the most recent build.
+    #[getset(get = "pub")]
+    entries: Vec<Entry>,
+}
+
+impl History {
+    /// Loads the history from the given directory.
+    ///
+    /// A missing history file is not an error, since it is only created after the first build.
+    pub fn load(directory: &Path) -> Result<Self, Error> {
+        let path = directory.join(HISTORY_FILE);
+
+        if !path.exists() {
+            return Ok(History::default());
+        }
+
+        History::parse(&read_to_string(path)?)
+    }
+
+    /// Writes the history to the given directory.
+    pub fn write(&self, directory: &Path) -> Result<(), Error> {
+        let mut file = File::create(directory.join(HISTORY_FILE))?;
+
+        let mut content = String::new();
+        for entry in &self.entries {
+            let destination = match &entry.destination {
+                Some(destination) => destination.to_string_lossy(),
+                None => Default::default(),
+            };
+
+            content.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                entry.timestamp, destination, entry.options, entry.query
+            ));
+        }
+
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    }
+
+    /// Records a build in the history.
+    ///
+    /// A query that was built before with the same options is moved to the top of the history
+    /// instead of being added a second time, so that the history shows as many different
+    /// combinations as possible. The destination is `None` for builds into a temporary file.
+    pub fn record(&mut self, query: &str, options: &str, destination: Option<&Path>) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        self.entries
+            .retain(|entry| entry.query != query || entry.options != options);
+        self.entries.insert(
+            0,
+            Entry {
+                query: String::from(query),
+                options: String::from(options),
+                timestamp,
+                destination: destination.map(Path::to_path_buf),
+            },
+        );
+        self.entries.truncate(MAX_ENTRIES);
+    }
+
+    /// Parses the content of a history file.
+    ///
+    /// Entries that were written before the options were recorded have no options field, and are
+    /// repeated with the current configuration.
+    fn parse(content: &str) -> Result<Self, Error> {
+        let entries = content
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let fields: Vec<&str> = line.splitn(4, '\t').collect();
+
+                let (options, query) = match fields.len() {
+                    3 => ("", fields[2]),
+                    4 => (fields[2], fields[3]),
+                    _ => {
+                        return Err(Error::new(
+                            ErrorKind::InvalidData,
+                            format!("The history contains an invalid entry '{line}'"),
+                        ))
+                    }
+                };
+
+                Ok(Entry {
+                    query: String::from(query),
+                    options: String::from(options),
+                    timestamp: fields[0]
+                        .parse()
+                        .map_err(|error| Error::new(ErrorKind::InvalidData, error))?,
+                    destination: Some(fields[1])
+                        .filter(|destination| !destination.is_empty())
+                        .map(PathBuf::from),
+                })
+            })
+            .collect::<Result<Vec<Entry>, Error>>()?;
+
+        Ok(History { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::history::History;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    #[test]
+    fn load_without_history() {
+        let tempdir = TempDir::new().unwrap();
+
+        assert!(History::load(tempdir.path()).unwrap().entries().is_empty());
+    }
+
+    #[test]
+    fn record_and_load() {
+        let tempdir = TempDir::new().unwrap();
+
+        let mut history = History::default();
+        history.record(
+            "Apples Oranges",
+            "--order alphabetical --dedupe",
+            Some(Path::new("/tmp/one.gitignore")),
+        );
+        history.record("Oranges", "", None);
+        history.write(tempdir.path()).unwrap();
+
+        let history = History::load(tempdir.path()).unwrap();
+        let queries: Vec<&String> = history.entries().iter().map(|e| e.query()).collect();
+
+        assert_eq!(vec!["Oranges", "Apples Oranges"], queries);
+        assert_eq!(
+            &Some(PathBuf::from("/tmp/one.gitignore")),
+            history.entries().get(1).unwrap().destination()
+        );
+        assert_eq!(&None, history.entries().first().unwrap().destination());
+    }
+
+    #[test]
+    fn arguments() {
+        let mut history = History::default();
+        history.record("Apples", "", None);
+        history.record(
+            "--extra .env Apples",
+            "--style minified",
+            Some(Path::new("/tmp/My Project/.gitignore")),
+        );
+
+        let arguments: Vec<String> = history.entries().iter().map(|e| e.arguments()).collect();
+
+        assert_eq!(
+            vec![
+                "--build --style minified --output \"/tmp/My Project/.gitignore\" --extra .env Apples",
+                "--build Apples",
+            ],
+            arguments
+        );
+    }
+
+    #[test]
+    fn record_moves_repeated_query_to_top() {
+        let mut history = History::default();
+        history.record("Apples", "", None);
+        history.record("Oranges", "", None);
+        history.record("Apples", "--dedupe", None);
+        history.record("Apples", "", None);
+
+        assert_eq!(3, history.entries().len());
+        assert_eq!("Apples", history.entries().first().unwrap().query());
+        assert_eq!("", history.entries().first().unwrap().options());
+    }
+
+    #[test]
+    fn parse_entry_without_options() {
+        let history = History::parse("1700000000\t/tmp/one.gitignore\tApples\n").unwrap();
+        let entry = history.entries().first().unwrap();
+
+        assert_eq!("Apples", entry.query());
+        assert_eq!("", entry.options());
+        assert_eq!(
+            "--build --output /tmp/one.gitignore Apples",
+            entry.arguments()
+        );
+    }
+
+    #[test]
+    fn parse_invalid_entry() {
+        assert!(History::parse("apples").is_err());
+        assert!(History::parse("yesterday\t/tmp/one.gitignore\tApples").is_err());
+    }
+
+    #[test]
+    fn age() {
+        let mut history = History::default();
+        history.record("Apples", "", None);
+        let entry = history.entries().first().unwrap();
+        let timestamp = entry.timestamp();
+
+        assert_eq!("just now", entry.age(timestamp + 30));
+        assert_eq!("1 minute ago", entry.age(timestamp + 60));
+        assert_eq!("3 hours ago", entry.age(timestamp + 3 * 3_600));
+        assert_eq!("2 days ago", entry.age(timestamp + 2 * 86_400));
+    }
+}
@@ -0,0 +1,26 @@
+//! Icons for Alfred items.
+//!
+//! The workflow does not ship its own icons, and instead uses the system icons that macOS provides
+//! in the `CoreTypes` bundle.
+
+const CORE_TYPES: &str = "/System/Library/CoreServices/CoreTypes.bundle/Contents/Resources/";
+
+/// Returns the path to the icon for presets.
+pub fn preset() -> String {
+    format!("{CORE_TYPES}GenericFolderIcon.icns")
+}
+
+/// Returns the path to the icon for favorite templates.
+pub fn favorite() -> String {
+    format!("{CORE_TYPES}ToolbarFavoritesIcon.icns")
+}
+
+/// Returns the path to the icon for notices.
+pub fn info() -> String {
+    format!("{CORE_TYPES}ToolbarInfo.icns")
+}
+
+/// Returns the path to the icon for warnings.
+pub fn warning() -> String {
+    format!("{CORE_TYPES}AlertCautionIcon.icns")
+}
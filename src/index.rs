@@ -0,0 +1,448 @@
+use crate::repository::Template;
+use getset::{CopyGetters, Getters};
+use sha2::{Digest, Sha256};
+use std::fs::{read, read_dir, read_to_string, File};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, MAIN_SEPARATOR};
+
+/// The name of the index file in the repository.
+pub const INDEX_FILE: &str = "index";
+
+/// The first line of the index file, which identifies the version of its format.
+const INDEX_HEADER: &str = "# alfred-gitignore index v5";
+
+/// The maximum length of a template's description.
+const MAX_DESCRIPTION_LENGTH: usize = 100;
+
+/// An entry in the index describes a single template.
+#[derive(Clone, Debug, Getters, CopyGetters)]
+pub struct Entry {
+    /// Returns the template that the entry describes.
+    #[getset(get = "pub")]
+    template: Template,
+
+    /// Returns the alias under which the template can be looked up unambiguously.
+    #[getset(get = "pub")]
+    alias: String,
+
+    /// Returns the size of the template in bytes.
+    #[getset(get_copy = "pub")]
+    size: u64,
+
+    /// Returns the SHA-256 hash of the template's content.
+    #[getset(get = "pub")]
+    hash: String,
+}
+
+/// The index of the templates in a repository.
+///
+/// Alfred runs the workflow for every key that the user types, and scanning the repository each
+/// time gets slower the more templates it contains. The index caches the templates and their
+/// metadata in a single file, which is rebuilt when the repository is updated and whenever the
+/// repository has changed since the index was written.
+#[derive(Debug, Getters)]
+pub struct Index {
+    /// Returns the entries in the index, sorted by the names of their templates.
+    #[getset(get = "pub")]
+    entries: Vec<Entry>,
+}
+
+impl Index {
+    /// Builds a new index by scanning the templates in the given directory.
+    ///
+    /// The directory is scanned recursively, since templates for editors and operating systems are
+    /// organized in subdirectories.
+    pub fn build(directory: &Path) -> Result<Self, Error> {
+        let mut entries = Vec::new();
+        Index::scan(directory, directory, &mut entries)?;
+
+        entries.sort_by(|a, b| {
+            a.template
+                .name()
+                .cmp(b.template.name())
+                .then_with(|| a.template.qualified_name().cmp(b.template.qualified_name()))
+        });
+
+        Ok(Index { entries })
+    }
+
+    /// Adds the templates in a directory and its subdirectories to the entries.
+    fn scan(root: &Path, directory: &Path, entries: &mut Vec<Entry>) -> Result<(), Error> {
+        for entry in read_dir(directory)? {
+            let entry = entry?;
+            let path = entry.path();
+
+            if entry.file_type()?.is_dir() {
+                Index::scan(root, &path, entries)?;
+                continue;
+            }
+
+            let relative_path = match path.strip_prefix(root).ok().and_then(|path| path.to_str()) {
+                Some(relative_path) => relative_path.replace(MAIN_SEPARATOR, "/"),
+                None => continue,
+            };
+
+            let file_name = entry.file_name();
+            if file_name == ".gitignore" || !relative_path.ends_with(".gitignore") {
+                continue;
+            }
+
+            let mut template = Template::new(&relative_path);
+            let content = read(&path)?;
+            template.set_description(Index::describe(&String::from_utf8_lossy(&content)));
+
+            entries.push(Entry {
+                alias: template.qualified_name().to_lowercase(),
+                size: content.len() as u64,
+                hash: format!("{:x}", Sha256::digest(&content)),
+                template,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Loads the index from the given directory.
+    ///
+    /// `None` is returned when the directory does not have an index yet, when the index is not
+    /// newer than the directory and thus might be missing templates that were added or removed, or
+    /// when the index was written by a version of the workflow with a different format. An index
+    /// that was written in the same tick as the last change to the directory is not trusted, since
+    /// the timestamps cannot tell which happened first.
+    ///
+    /// Only the directory itself is checked, since the index is loaded on every keystroke. Changes
+    /// to subdirectories or to the content of a template are picked up when the repository is
+    /// updated, which always rebuilds the index.
+    pub fn load(directory: &Path) -> Result<Option<Self>, Error> {
+        let path = directory.join(INDEX_FILE);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        if path.metadata()?.modified()? <= directory.metadata()?.modified()? {
+            return Ok(None);
+        }
+
+        let content = read_to_string(path)?;
+        if content.lines().next() != Some(INDEX_HEADER) {
+            return Ok(None);
+        }
+
+        Index::parse(&content).map(Some)
+    }
+
+    /// Writes the index to the given directory.
+    pub fn write(&self, directory: &Path) -> Result<(), Error> {
+        let mut file = File::create(directory.join(INDEX_FILE))?;
+
+        let mut content = format!("{INDEX_HEADER}\n");
+        for entry in &self.entries {
+            content.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                entry.template.path(),
+                entry.alias,
+                entry.template.category(),
+                entry.template.tags().join(","),
+                entry.size,
+                entry.hash,
+                entry.template.description().as_deref().unwrap_or_default()
+            ));
+        }
+
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    }
+
+    /// Returns the templates in the index.
+    pub fn templates(&self) -> Vec<Template> {
+        self.entries
+            .iter()
+            .map(|entry| entry.template.clone())
+            .collect()
+    }
+
+    /// Returns a description of a template based on its content.
+    ///
+    /// Most templates start with a block of comments that explains what the template is for. The
+    /// lines of the first paragraph of this block are joined into a single line, and shortened if
+    /// they are too long to be displayed in Alfred. Later paragraphs are usually notes for the
+    /// contributors of the template. Lines that only draw a separator, editor modelines like
+    /// `-*- mode: gitignore; -*-`, and the `#` that closes a boxed comment are skipped.
+    fn describe(content: &str) -> Option<String> {
+        let mut words: Vec<&str> = Vec::new();
+
+        let comments = content
+            .lines()
+            .map(str::trim)
+            .skip_while(|line| line.is_empty())
+            .take_while(|line| line.starts_with('#'));
+        for line in comments {
+            let text = line.trim_matches('#').trim();
+
+            if text.contains("-*-") || text.chars().all(|c| "-=#*~ ".contains(c)) {
+                // A comment without text ends the paragraph, unless the paragraph has not started
+                if text.is_empty() && !words.is_empty() {
+                    break;
+                }
+
+                continue;
+            }
+
+            words.extend(text.split_whitespace());
+        }
+
+        if words.is_empty() {
+            return None;
+        }
+
+        let description = words.join(" ");
+        if description.chars().count() <= MAX_DESCRIPTION_LENGTH {
+            return Some(description);
+        }
+
+        let shortened: String = description
+            .chars()
+            .take(MAX_DESCRIPTION_LENGTH - 1)
+            .collect();
+        Some(format!("{}…", shortened.trim_end()))
+    }
+
+    /// Parses the content of an index file.
+    fn parse(content: &str) -> Result<Self, Error> {
+        let mut lines = content.lines();
+
+        if lines.next() != Some(INDEX_HEADER) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "The index has an unknown format",
+            ));
+        }
+
+        let entries = lines
+            .map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+
+                if fields.len() != 7 {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("The index contains an invalid entry '{line}'"),
+                    ));
+                }
+
+                let mut template = Template::new(fields[0]);
+                template
+                    .set_category(fields[2].parse()?)
+                    .set_tags(fields[3].split(',').map(String::from).collect())
+                    .set_description(Some(String::from(fields[6])).filter(|d| !d.is_empty()));
+
+                Ok(Entry {
+                    template,
+                    alias: String::from(fields[1]),
+                    size: fields[4]
+                        .parse()
+                        .map_err(|error| Error::new(ErrorKind::InvalidData, error))?,
+                    hash: String::from(fields[5]),
+                })
+            })
+            .collect::<Result<Vec<Entry>, Error>>()?;
+
+        Ok(Index { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::category::Category;
+    use crate::index::Index;
+    use crate::testing::initialize_repository;
+    use std::fs::{create_dir, File};
+    use std::io::Write;
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    /// Sets the modification time of the index in the given directory.
+    fn set_index_modified(directory: &Path, modified: SystemTime) {
+        File::options()
+            .write(true)
+            .open(directory.join("index"))
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    /// Returns the modification time of the given directory.
+    fn modified(directory: &Path) -> SystemTime {
+        directory.metadata().unwrap().modified().unwrap()
+    }
+
+    #[test]
+    fn build() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+
+        let index = Index::build(repository.path()).unwrap();
+        let entry = index.entries().first().unwrap();
+
+        assert_eq!(2, index.entries().len());
+        assert_eq!("apples", entry.template().name());
+        assert_eq!("apples", entry.alias());
+        assert_eq!(Category::Language, entry.template().category());
+        assert_eq!(
+            &Some(String::from("This file ignores apples")),
+            entry.template().description()
+        );
+        assert_eq!(35, entry.size());
+        assert_eq!(64, entry.hash().len());
+    }
+
+    #[test]
+    fn write_and_load() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+
+        let index = Index::build(repository.path()).unwrap();
+        index.write(repository.path()).unwrap();
+        set_index_modified(
+            repository.path(),
+            modified(repository.path()) + Duration::from_secs(1),
+        );
+
+        let loaded = Index::load(repository.path()).unwrap().unwrap();
+
+        assert_eq!(index.entries().len(), loaded.entries().len());
+        let entry = index.entries().first().unwrap();
+        let loaded_entry = loaded.entries().first().unwrap();
+
+        assert_eq!(entry.hash(), loaded_entry.hash());
+        assert_eq!(entry.template().tags(), loaded_entry.template().tags());
+        assert_eq!(
+            entry.template().description(),
+            loaded_entry.template().description()
+        );
+    }
+
+    #[test]
+    fn build_with_subdirectories() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        create_dir(repository.path().join("Global")).unwrap();
+        File::create(repository.path().join("Global/macOS.gitignore")).unwrap();
+        File::create(repository.path().join(".gitignore")).unwrap();
+
+        let index = Index::build(repository.path()).unwrap();
+        let entry = index.entries().get(1).unwrap();
+
+        assert_eq!(3, index.entries().len());
+        assert_eq!("Global/macOS", entry.template().qualified_name());
+        assert_eq!("global/macos", entry.alias());
+    }
+
+    #[test]
+    fn load_without_index() {
+        let tempdir = TempDir::new().unwrap();
+
+        assert!(Index::load(tempdir.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn load_outdated_index() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+
+        Index::build(repository.path())
+            .unwrap()
+            .write(repository.path())
+            .unwrap();
+
+        File::create(repository.path().join("peaches.gitignore")).unwrap();
+        set_index_modified(repository.path(), modified(repository.path()));
+
+        assert!(Index::load(repository.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn load_index_with_changed_subdirectory() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        create_dir(repository.path().join("Global")).unwrap();
+
+        Index::build(repository.path())
+            .unwrap()
+            .write(repository.path())
+            .unwrap();
+
+        File::create(repository.path().join("Global/macOS.gitignore")).unwrap();
+        set_index_modified(
+            repository.path(),
+            modified(repository.path()) + Duration::from_secs(1),
+        );
+
+        assert!(Index::load(repository.path()).unwrap().is_some());
+    }
+
+    #[test]
+    fn parse_invalid_header() {
+        assert!(Index::parse("apples.gitignore").is_err());
+    }
+
+    #[test]
+    fn load_index_with_old_format() {
+        let tempdir = TempDir::new().unwrap();
+        let mut file = File::create(tempdir.path().join("index")).unwrap();
+        file.write_all(b"# alfred-gitignore index v1\n").unwrap();
+        file.sync_all().unwrap();
+
+        assert!(Index::load(tempdir.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn describe() {
+        let content = "\n# Compiled files\n#   and caches\n*.o\n# Logs\n*.log\n";
+
+        assert_eq!(
+            Some(String::from("Compiled files and caches")),
+            Index::describe(content)
+        );
+        assert_eq!(None, Index::describe("*.o\n# Logs\n"));
+    }
+
+    #[test]
+    fn describe_boxed_comment() {
+        let content = "#--------------------------#\n# Magento Default Files    #\n#--------------------------#\n\n/PATCH_*.sh\n";
+
+        assert_eq!(
+            Some(String::from("Magento Default Files")),
+            Index::describe(content)
+        );
+        assert_eq!(
+            Some(String::from("Swap Files")),
+            Index::describe("# Swap Files #\n.*.kate-swp\n.swp.*\n")
+        );
+    }
+
+    #[test]
+    fn describe_modeline() {
+        let content = "# -*- mode: gitignore; -*-\n*~\n\\#*\\#\n";
+
+        assert_eq!(None, Index::describe(content));
+    }
+
+    #[test]
+    fn describe_first_paragraph() {
+        let content = "# Xcode\n#\n# gitignore contributors: remember to update Global/Xcode.gitignore, Objective-C.gitignore & Swift.gitignore\n\n## User settings\nxcuserdata/\n";
+
+        assert_eq!(Some(String::from("Xcode")), Index::describe(content));
+    }
+
+    #[test]
+    fn describe_long_comment() {
+        let content = format!("# {}\n", "word ".repeat(50));
+
+        let description = Index::describe(&content).unwrap();
+
+        assert_eq!(100, description.chars().count());
+        assert!(description.ends_with("word…"));
+    }
+}
@@ -1,25 +1,54 @@
-use crate::command::build::Build;
+use crate::command::build::{Build, Options};
+use crate::command::check::Check;
+use crate::command::favorite::Favorite;
+use crate::command::refresh::Refresh;
 use crate::command::select::Select;
 use crate::command::update::Update;
+use crate::detector::Detector;
 use crate::repository::Repository;
 use alfred::ItemBuilder;
-use clap::{crate_version, App, Arg};
+use clap::{crate_version, App, AppSettings, Arg};
 use std::io::{stdout, Error, ErrorKind};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 
 mod command;
 
+mod analyzer;
 mod builder;
+mod category;
+mod config;
+mod detector;
+mod diff;
+mod favorites;
+mod header;
+mod history;
+mod icon;
+mod index;
 mod query;
 mod repository;
+mod section;
 
 #[cfg(test)]
 mod testing;
 
+const DEDUPE_ARG: &str = "dedupe";
+const DIFF_ARG: &str = "diff";
+const EXTRA_ARG: &str = "extra";
+const FORCE_ARG: &str = "force";
+const HEADER_ARG: &str = "header";
+const MERGE_ARG: &str = "merge";
+const ORDER_ARG: &str = "order";
+const OUTPUT_ARG: &str = "output";
+const RAW_ARG: &str = "raw";
+const STYLE_ARG: &str = "style";
 const TEMPLATES_ARG: &str = "TEMPLATES";
 
 const BUILD_COMMAND: &str = "build";
+const CHECK_COMMAND: &str = "check";
+const DETECT_COMMAND: &str = "detect";
+const FAVORITE_COMMAND: &str = "favorite";
+const REFRESH_COMMAND: &str = "refresh";
 const UPDATE_COMMAND: &str = "update";
 
 fn main() {
@@ -27,6 +56,7 @@ fn main() {
         .version(crate_version!())
         .author("Jan David <jandavid@6a64.com>")
         .about("An Alfred workflow to generate .gitignore files")
+        .setting(AppSettings::TrailingVarArg)
         .arg(
             Arg::with_name("repository")
                 .help("Provide a custom directory for development and testing")
@@ -34,12 +64,100 @@ fn main() {
                 .long("repository")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name(ORDER_ARG)
+                .help("Set the order of the templates in the .gitignore file")
+                .short("o")
+                .long(ORDER_ARG)
+                .takes_value(true)
+                .possible_values(&["as-typed", "alphabetical", "category"]),
+        )
+        .arg(
+            Arg::with_name(STYLE_ARG)
+                .help("Set the style in which the templates are written")
+                .long(STYLE_ARG)
+                .takes_value(true)
+                .possible_values(&["full", "minified"]),
+        )
+        .arg(
+            Arg::with_name(EXTRA_ARG)
+                .help("Append a custom pattern to the .gitignore file")
+                .short("e")
+                .long(EXTRA_ARG)
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name(DEDUPE_ARG)
+                .help("Write patterns that are repeated across templates only once")
+                .long(DEDUPE_ARG),
+        )
+        .arg(
+            Arg::with_name(HEADER_ARG)
+                .help("Write a header and the source of each template to the .gitignore file")
+                .long(HEADER_ARG),
+        )
+        .arg(
+            Arg::with_name(OUTPUT_ARG)
+                .help("Write the .gitignore file to the given directory or file")
+                .long(OUTPUT_ARG)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(FORCE_ARG)
+                .help("Overwrite an existing file at the output without asking")
+                .long(FORCE_ARG),
+        )
+        .arg(
+            Arg::with_name(MERGE_ARG)
+                .help("Update only the templates in an existing file at the output")
+                .long(MERGE_ARG),
+        )
+        .arg(
+            Arg::with_name(DIFF_ARG)
+                .help("Show the changes to an existing file at the output instead of writing it")
+                .long(DIFF_ARG),
+        )
+        .arg(
+            Arg::with_name(RAW_ARG)
+                .help("Print the .gitignore file to stdout instead of Alfred items")
+                .long(RAW_ARG)
+                .requires(BUILD_COMMAND)
+                .conflicts_with_all(&[OUTPUT_ARG, DIFF_ARG]),
+        )
         .arg(
             Arg::with_name(BUILD_COMMAND)
                 .help("Create a single .gitignore file from the templates")
                 .short("b")
                 .long("build"),
         )
+        .arg(
+            Arg::with_name(CHECK_COMMAND)
+                .help("Check the templates for patterns that override each other")
+                .short("c")
+                .long(CHECK_COMMAND),
+        )
+        .arg(
+            Arg::with_name(DETECT_COMMAND)
+                .help("Detect the templates for the project in the given directory")
+                .short("d")
+                .long(DETECT_COMMAND)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(FAVORITE_COMMAND)
+                .help("Add the given template to the favorites, or remove it")
+                .short("f")
+                .long(FAVORITE_COMMAND)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(REFRESH_COMMAND)
+                .help("Rebuild the templates in an existing .gitignore file")
+                .long(REFRESH_COMMAND)
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name(UPDATE_COMMAND)
                 .help("Update the repository or workflow data directory")
@@ -48,30 +166,132 @@ fn main() {
         )
         .arg(
             Arg::with_name(TEMPLATES_ARG)
-                .help("Provide a list of templates")
-                .multiple(true),
+                .help("Provide a list of templates, and patterns to exclude prefixed with '-'")
+                .multiple(true)
+                .allow_hyphen_values(true),
         )
-        .get_matches();
+        .get_matches_from(join_quoted(std::env::args().collect()));
 
-    let repository = initialize_repository(matches.value_of("repository"));
+    let raw = matches.is_present(RAW_ARG);
+    let templates: Option<Vec<&str>> = matches
+        .values_of(TEMPLATES_ARG)
+        .map(|values| values.collect());
+
+    // Options after the first template are collected as templates, and would otherwise be ignored
+    if let Some(option) = templates
+        .iter()
+        .flatten()
+        .find(|template| template.starts_with("--"))
+    {
+        let exit = exit_for(raw || *option == "--raw");
+        exit(&Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unexpected option '{option}' after the templates, move it before them"),
+        ));
+    }
+
+    let repository = initialize_repository(matches.value_of("repository"), raw);
+    let extras: Vec<&str> = matches
+        .values_of(EXTRA_ARG)
+        .map(|values| values.collect())
+        .unwrap_or_default();
+
+    let mut options = Options::default();
+    options
+        .set_order(
+            matches
+                .value_of(ORDER_ARG)
+                .map(|order| order.parse().unwrap_or_else(|error| exit_for(raw)(&error))),
+        )
+        .set_style(
+            matches
+                .value_of(STYLE_ARG)
+                .map(|style| style.parse().unwrap_or_else(|error| exit_for(raw)(&error))),
+        )
+        .set_extras(extras.clone())
+        .set_dedupe(matches.is_present(DEDUPE_ARG))
+        .set_header(matches.is_present(HEADER_ARG))
+        .set_output(matches.value_of(OUTPUT_ARG).map(PathBuf::from))
+        .set_force(matches.is_present(FORCE_ARG))
+        .set_merge(matches.is_present(MERGE_ARG))
+        .set_diff(matches.is_present(DIFF_ARG))
+        .set_raw(raw);
 
     if matches.is_present(BUILD_COMMAND) {
-        Build::perform(repository, matches.values_of(TEMPLATES_ARG));
+        Build::perform(repository, templates, options);
+    }
+
+    if let Some(path) = matches.value_of(REFRESH_COMMAND) {
+        Refresh::perform(&repository, Path::new(path), options);
+    }
+
+    if matches.is_present(CHECK_COMMAND) {
+        Check::perform(&repository, templates, options);
     }
 
     if matches.is_present(UPDATE_COMMAND) {
         Update::perform(&repository);
     }
 
-    Select::perform(&repository, matches.values_of(TEMPLATES_ARG));
+    // Alfred splits a quoted name into multiple arguments, which are joined back together
+    let mut templates = templates;
+    if let Some(favorite) = matches.value_of(FAVORITE_COMMAND) {
+        let mut arguments = vec![favorite];
+        arguments.extend(templates.iter().flatten());
+        let (template, query) = arguments.split_at(query::span(&arguments));
+
+        Favorite::perform(&repository, &template.join(" "));
+        templates = templates.map(|_| query.to_vec());
+    }
+
+    if let Some(directory) = matches.value_of(DETECT_COMMAND) {
+        let detected = match Detector::new(PathBuf::from(directory)).templates() {
+            Ok(detected) => detected,
+            Err(error) => exit_with_error(&error),
+        };
+
+        let mut selections: Vec<&str> = detected.iter().map(String::as_str).collect();
+        selections.extend(templates.unwrap_or_default());
+
+        Select::perform(&repository, Some(selections), &extras);
+    }
+
+    Select::perform(&repository, templates, &extras);
+}
+
+/// Joins the values of options that Alfred split into multiple arguments.
+///
+/// Custom patterns and paths that contain whitespace are wrapped in double quotes in the query,
+/// and would otherwise be cut off at the first space, with the rest being mistaken for templates.
+fn join_quoted(arguments: Vec<String>) -> Vec<String> {
+    let mut joined: Vec<String> = Vec::new();
+    let mut remaining: Vec<&str> = arguments.iter().map(String::as_str).collect();
+
+    while !remaining.is_empty() {
+        if matches!(
+            joined.last().map(String::as_str),
+            Some("--extra" | "-e" | "--output" | "--refresh")
+        ) {
+            let length = query::span(&remaining);
+
+            joined.push(query::tokenize(&remaining[..length]).concat());
+            remaining.drain(..length);
+        } else {
+            joined.push(String::from(remaining.remove(0)));
+        }
+    }
+
+    joined
 }
 
-fn initialize_repository(path: Option<&str>) -> Repository {
+fn initialize_repository(path: Option<&str>, raw: bool) -> Repository {
+    let exit = exit_for(raw);
+
     let repository_path = match path {
         Some(path) => PathBuf::from(path),
         None => match alfred::env::workflow_data() {
             Some(path) => path,
-            None => exit_with_error(&Error::new(
+            None => exit(&Error::new(
                 ErrorKind::NotFound,
                 "Alfred did not provide a data directory to the workflow",
             )),
@@ -80,7 +300,16 @@ fn initialize_repository(path: Option<&str>) -> Repository {
 
     match Repository::new(repository_path) {
         Ok(repository) => repository,
-        Err(error) => exit_with_error(&error),
+        Err(error) => exit(&error),
+    }
+}
+
+/// Returns the function that reports an error, depending on whether Alfred reads the output.
+fn exit_for(raw: bool) -> fn(&Error) -> ! {
+    if raw {
+        exit_with_raw_error
+    } else {
+        exit_with_error
     }
 }
 
@@ -95,3 +324,10 @@ fn exit_with_error(error: &Error) -> ! {
 
     exit(1);
 }
+
+/// Prints the error to stderr and exits, for when the output is not read by Alfred.
+fn exit_with_raw_error(error: &Error) -> ! {
+    eprintln!("error: {error}");
+
+    exit(1);
+}
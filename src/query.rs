@@ -1,18 +1,160 @@
+use crate::category::Category;
+use crate::config::Preset;
+use crate::favorites::Favorites;
 use crate::repository::{Repository, Template};
+use getset::{CopyGetters, Getters};
 use std::collections::HashMap;
 use std::io::Error;
 
+/// The maximum number of corrections that are suggested for a typo.
+const MAX_CORRECTIONS: usize = 3;
+
+/// The edit distance that is always accepted for a correction, even for short inputs.
+const MIN_CORRECTION_DISTANCE: usize = 2;
+
+/// The prefix that marks an element of the query as a pattern to exclude.
+const EXCLUSION_PREFIX: char = '-';
+
+/// The prefix of a qualifier that narrows the suggestions to a category, e.g. `cat:editor`.
+pub const CATEGORY_PREFIX: &str = "cat:";
+
+/// An exclusion removes a pattern from a template.
+///
+/// Exclusions are added to the query by prefixing a pattern with `-` after the template that
+/// contains it, for example `Node -dist/`. Presets can contain exclusions, too.
+#[derive(Clone, Debug, Eq, PartialEq, Getters)]
+pub struct Exclusion {
+    /// Returns the pattern that is excluded.
+    #[getset(get = "pub")]
+    pattern: String,
+
+    /// Returns an explanation where the exclusion comes from.
+    #[getset(get = "pub")]
+    reason: String,
+}
+
+/// The kind of a suggestion.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Kind {
+    /// The suggestion is a single template.
+    Template,
+
+    /// The suggestion is a preset that expands to multiple templates.
+    Preset,
+
+    /// The suggestion is a qualifier that narrows the suggestions to a category.
+    Category,
+}
+
+/// A suggestion for the user's input.
+#[derive(Clone, Debug, Getters, CopyGetters)]
+pub struct Suggestion {
+    /// Returns the name of the suggestion.
+    #[getset(get = "pub")]
+    name: String,
+
+    /// Returns the kind of the suggestion.
+    #[getset(get_copy = "pub")]
+    kind: Kind,
+
+    /// Returns a short description of the suggestion.
+    #[getset(get = "pub")]
+    description: Option<String>,
+
+    /// Returns the text that adds the suggestion to the query.
+    #[getset(get = "pub")]
+    address: String,
+
+    /// Returns whether the suggestion is a template that the user marked as a favorite.
+    #[getset(get_copy = "pub")]
+    favorite: bool,
+
+    comparator: String,
+    qualified_comparator: String,
+}
+
+impl Suggestion {
+    /// Returns a suggestion for a template.
+    ///
+    /// The location of the template is added to the description when other templates share its
+    /// name, so that the user can tell them apart.
+    fn from_template(template: &Template, address: String, location: Option<&str>) -> Self {
+        let description = match (location, template.description()) {
+            (Some(location), Some(description)) => Some(format!("{location}: {description}")),
+            (Some(location), None) => Some(String::from(location)),
+            (None, description) => description.clone(),
+        };
+
+        Suggestion {
+            name: template.name().clone(),
+            kind: Kind::Template,
+            description,
+            address,
+            favorite: false,
+            comparator: template.comparator().clone(),
+            qualified_comparator: template.qualified_name().to_lowercase(),
+        }
+    }
+
+    fn from_preset(preset: &Preset) -> Self {
+        Suggestion {
+            name: preset.name().clone(),
+            kind: Kind::Preset,
+            description: Some(format!("Preset: {}", preset.templates().join(", "))),
+            address: quote(preset.name()),
+            favorite: false,
+            comparator: preset.comparator().clone(),
+            qualified_comparator: preset.comparator().clone(),
+        }
+    }
+
+    fn from_category(category: Category) -> Self {
+        let qualifier = format!("{CATEGORY_PREFIX}{category}");
+
+        Suggestion {
+            name: qualifier.clone(),
+            kind: Kind::Category,
+            description: Some(format!(
+                "Only suggest templates in the category '{category}'"
+            )),
+            address: qualifier.clone(),
+            favorite: false,
+            comparator: qualifier.clone(),
+            qualified_comparator: qualifier,
+        }
+    }
+
+    /// Returns whether the suggestion starts with the given input.
+    fn starts_with(&self, input: &str) -> bool {
+        self.comparator.starts_with(input) || self.qualified_comparator.starts_with(input)
+    }
+}
+
 /// Represents a user's input.
 ///
 /// The query represents a user's input, and provides abstractions to interact with the input in a
 /// meaningful way. First, it provides a way to sanitize the input and return only the elements that
 /// match templates in the repository. Second, it can make suggestions for the last phrase the user
 /// is entering.
-#[derive(Debug)]
+///
+/// Templates can be addressed by their name, e.g. `macOS`, or by their qualified name, e.g.
+/// `Global/macOS`, which is unique even if templates in different directories share a name. Names
+/// that contain whitespace can be wrapped in double quotes.
+///
+/// A qualifier like `cat:editor` narrows the suggestions to the templates in a category. It does
+/// not select any templates itself.
+#[derive(Debug, CopyGetters)]
 pub struct Query {
     query: Vec<String>,
     query_map: HashMap<String, String>,
-    templates_map: HashMap<String, Template>,
+    templates_map: HashMap<String, Vec<Template>>,
+    namesakes_map: HashMap<String, usize>,
+    presets_map: HashMap<String, Preset>,
+    favorites: Favorites,
+
+    /// Returns the category that the suggestions are narrowed to.
+    #[getset(get_copy = "pub")]
+    category: Option<Category>,
 }
 
 impl Query {
@@ -22,7 +164,7 @@ impl Query {
     /// suggestions, and filter the list of templates.
     pub fn new(repository: &Repository, query: Option<Vec<&str>>) -> Result<Self, Error> {
         let query = match query {
-            Some(values) => values.iter().map(|str| String::from(*str)).collect(),
+            Some(values) => tokenize(&values),
             None => Vec::new(),
         };
 
@@ -31,84 +173,542 @@ impl Query {
             query_map.insert(string.to_lowercase(), string.clone());
         }
 
-        let mut templates_map = HashMap::new();
+        let mut templates_map: HashMap<String, Vec<Template>> = HashMap::new();
+        let mut namesakes_map: HashMap<String, usize> = HashMap::new();
         let templates = repository.templates()?;
         for template in templates {
-            templates_map.insert(template.name().to_lowercase(), template);
+            *namesakes_map
+                .entry(template.comparator().clone())
+                .or_default() += 1;
+            templates_map
+                .entry(template.qualified_name().to_lowercase())
+                .or_default()
+                .push(template);
         }
 
+        let mut presets_map = HashMap::new();
+        for preset in repository.config()?.presets() {
+            presets_map.insert(preset.comparator().clone(), preset.clone());
+        }
+
+        let category = query.iter().rev().find_map(|input| qualifier(input));
+
         Ok(Query {
             query,
             query_map,
             templates_map,
+            namesakes_map,
+            presets_map,
+            favorites: repository.favorites()?,
+            category,
         })
     }
 
+    /// Returns whether the user has not typed anything yet.
+    pub fn is_empty(&self) -> bool {
+        self.query.is_empty()
+    }
+
     /// Returns a list of valid template names.
     ///
     /// Since users can provide arbitrary strings as a query, this method exists to filter their
-    /// input to a list of templates that exist in the repository.
+    /// input to a list of templates that exist in the repository. Presets are expanded to the
+    /// templates they contain. Templates that are selected more than once are only returned once,
+    /// at the position where they were selected first.
     pub fn sanitized_query(&self) -> Vec<Template> {
-        self.query
-            .iter()
-            .filter_map(|input| {
-                let sanitized_input = input.to_lowercase();
-                self.templates_map.get(sanitized_input.as_str())
-            })
-            .cloned()
+        self.deduplicate().0
+    }
+
+    /// Returns the templates that were removed from the query because they were duplicates.
+    ///
+    /// Each template is returned once for every time it was selected again after its first
+    /// occurrence, for example when the user typed `apples Apples`.
+    pub fn duplicates(&self) -> Vec<Template> {
+        self.deduplicate().1
+    }
+
+    /// Returns the patterns that should be excluded from the templates in the query.
+    ///
+    /// The exclusions are grouped by the path of the template that they apply to. An exclusion
+    /// applies to the templates of the element that precedes it, which is either a single template
+    /// or all the templates in a preset.
+    pub fn exclusions(&self) -> HashMap<String, Vec<Exclusion>> {
+        let mut exclusions = HashMap::new();
+        self.collect_exclusions(&self.query, "Excluded by the query", &mut exclusions);
+
+        exclusions
+    }
+
+    /// Adds the exclusions in a list of elements to the given map.
+    fn collect_exclusions(
+        &self,
+        elements: &[String],
+        reason: &str,
+        exclusions: &mut HashMap<String, Vec<Exclusion>>,
+    ) {
+        let mut previous: Vec<Template> = Vec::new();
+
+        for element in elements {
+            if let Some(pattern) = element.strip_prefix(EXCLUSION_PREFIX) {
+                if pattern.is_empty() {
+                    continue;
+                }
+
+                for template in &previous {
+                    let exclusion = Exclusion {
+                        pattern: String::from(pattern),
+                        reason: String::from(reason),
+                    };
+                    let template_exclusions: &mut Vec<Exclusion> =
+                        exclusions.entry(template.path().clone()).or_default();
+
+                    if !template_exclusions.contains(&exclusion) {
+                        template_exclusions.push(exclusion);
+                    }
+                }
+            } else {
+                previous = self.resolve(element);
+
+                if self.find_template(element).is_none() {
+                    if let Some(preset) = self.presets_map.get(element.to_lowercase().as_str()) {
+                        let reason = format!("Excluded by preset {}", preset.name());
+                        self.collect_exclusions(preset.templates(), &reason, exclusions);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the exclusions in the query that do not follow a template or preset.
+    ///
+    /// An exclusion applies to the element that precedes it, so an exclusion at the start of the
+    /// query has no template to remove its pattern from and would otherwise be ignored silently.
+    pub fn unmatched_exclusions(&self) -> Vec<String> {
+        let mut unmatched = Vec::new();
+        let mut previous: Vec<Template> = Vec::new();
+
+        for element in &self.query {
+            match element.strip_prefix(EXCLUSION_PREFIX) {
+                Some(pattern) => {
+                    if !pattern.is_empty() && previous.is_empty() {
+                        unmatched.push(element.clone());
+                    }
+                }
+                None => previous = self.resolve(element),
+            }
+        }
+
+        unmatched
+    }
+
+    /// Returns the elements of the query that do not match a template or preset.
+    ///
+    /// These are most likely typos, and are returned in the order in which the user typed them so
+    /// that they can be reported back to the user. Templates in a preset that do not exist are
+    /// returned as well, since a typo in the configuration would otherwise go unnoticed.
+    pub fn unresolved(&self) -> Vec<String> {
+        let mut unresolved = Vec::new();
+
+        for input in &self.query {
+            if input.starts_with(EXCLUSION_PREFIX)
+                || qualifier(input).is_some()
+                || self.find_template(input).is_some()
+            {
+                continue;
+            }
+
+            match self.presets_map.get(input.to_lowercase().as_str()) {
+                Some(preset) => unresolved.extend(
+                    preset
+                        .templates()
+                        .iter()
+                        .filter(|name| {
+                            !name.starts_with(EXCLUSION_PREFIX)
+                                && self.find_template(name).is_none()
+                        })
+                        .cloned(),
+                ),
+                None => unresolved.push(input.clone()),
+            }
+        }
+
+        unresolved
+    }
+
+    /// Returns whether the user is still typing the last element of the query.
+    ///
+    /// The last element is considered incomplete when it does not match a template or preset yet,
+    /// but is the beginning of the name of at least one of them.
+    pub fn is_incomplete(&self) -> bool {
+        let last_element = match self.query.last() {
+            Some(last_element) => last_element,
+            None => return false,
+        };
+
+        if last_element.starts_with(EXCLUSION_PREFIX)
+            || qualifier(last_element).is_some()
+            || self.is_known(last_element)
+        {
+            return false;
+        }
+
+        let last_element = last_element.to_lowercase();
+
+        self.templates()
+            .map(|template| Suggestion::from_template(template, String::new(), None))
+            .chain(self.presets_map.values().map(Suggestion::from_preset))
+            .chain(Category::ALL.iter().copied().map(Suggestion::from_category))
+            .any(|suggestion| suggestion.starts_with(last_element.as_str()))
+    }
+
+    /// Returns the names of templates and presets that are similar to the given input.
+    ///
+    /// The similarity is measured by the edit distance between the input and the names. Only names
+    /// that are close enough to be a plausible typo are returned, with the closest match first.
+    pub fn corrections(&self, input: &str) -> Vec<String> {
+        let input = input.to_lowercase();
+        let threshold = (input.chars().count() / 3).max(MIN_CORRECTION_DISTANCE);
+
+        let mut corrections: Vec<(usize, &String)> = self
+            .templates()
+            .map(|template| (template.comparator(), template.name()))
+            .chain(
+                self.presets_map
+                    .values()
+                    .map(|preset| (preset.comparator(), preset.name())),
+            )
+            .map(|(comparator, name)| (edit_distance(&input, comparator), name))
+            .filter(|(distance, _name)| *distance <= threshold)
+            .collect();
+
+        corrections.sort();
+        corrections.dedup();
+        corrections
+            .into_iter()
+            .take(MAX_CORRECTIONS)
+            .map(|(_distance, name)| name.clone())
             .collect()
     }
 
+    /// Returns the text that adds the given template to a query.
+    ///
+    /// Templates are addressed by their name if it is unique, and by their qualified name if other
+    /// templates share the same name. The case of the name is preserved, which distinguishes
+    /// templates whose names only differ in case. Names that contain whitespace are wrapped in
+    /// quotes.
+    pub fn address(&self, template: &Template) -> String {
+        if self.namesakes(template) > 1 {
+            quote(template.qualified_name())
+        } else {
+            quote(template.name())
+        }
+    }
+
+    /// Returns whether the user marked the template as a favorite.
+    pub fn is_favorite(&self, template: &Template) -> bool {
+        self.favorites.contains(template.qualified_name())
+    }
+
+    /// Returns an iterator over all templates in the repository.
+    fn templates(&self) -> impl Iterator<Item = &Template> {
+        self.templates_map.values().flatten()
+    }
+
+    /// Returns the number of templates that have the same name as the given template, ignoring
+    /// case. The template itself is included in the count.
+    ///
+    /// The names are counted once when the query is created, since this is called for every
+    /// suggestion on every keystroke.
+    fn namesakes(&self, template: &Template) -> usize {
+        self.namesakes_map
+            .get(template.comparator())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns whether the input matches a template or preset.
+    fn is_known(&self, input: &str) -> bool {
+        self.find_template(input).is_some()
+            || self.presets_map.contains_key(input.to_lowercase().as_str())
+    }
+
+    /// Returns the template that matches the given input.
+    ///
+    /// Qualified names take precedence over names, since they are more specific. If multiple
+    /// templates match the input, the one whose name matches the case of the input is preferred,
+    /// followed by the one closest to the root of the repository.
+    fn find_template(&self, input: &str) -> Option<&Template> {
+        let sanitized_input = input.to_lowercase();
+
+        let candidates: Vec<&Template> = match self.templates_map.get(sanitized_input.as_str()) {
+            Some(templates) => templates.iter().collect(),
+            None => self
+                .templates()
+                .filter(|template| template.comparator() == &sanitized_input)
+                .collect(),
+        };
+
+        candidates.into_iter().min_by(|a, b| {
+            let a_exact = a.qualified_name() == input || a.name() == input;
+            let b_exact = b.qualified_name() == input || b.name() == input;
+
+            b_exact
+                .cmp(&a_exact)
+                .then_with(|| a.qualified_name().len().cmp(&b.qualified_name().len()))
+                .then_with(|| a.qualified_name().cmp(b.qualified_name()))
+        })
+    }
+
+    /// Splits the resolved templates into unique templates and duplicates.
+    fn deduplicate(&self) -> (Vec<Template>, Vec<Template>) {
+        let mut templates: Vec<Template> = Vec::new();
+        let mut duplicates: Vec<Template> = Vec::new();
+
+        for template in self.query.iter().flat_map(|input| self.resolve(input)) {
+            if templates
+                .iter()
+                .any(|selected| selected.path() == template.path())
+            {
+                duplicates.push(template);
+            } else {
+                templates.push(template);
+            }
+        }
+
+        (templates, duplicates)
+    }
+
+    /// Resolves a single element of the query to the templates it stands for.
+    ///
+    /// Templates take precedence over presets, so that a preset cannot shadow a template with the
+    /// same name.
+    fn resolve(&self, input: &str) -> Vec<Template> {
+        if let Some(template) = self.find_template(input) {
+            return vec![template.clone()];
+        }
+
+        match self.presets_map.get(input.to_lowercase().as_str()) {
+            Some(preset) => preset
+                .templates()
+                .iter()
+                .filter_map(|name| self.find_template(name))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
     /// Returns suggestions for the last element in the query.
     ///
     /// Alfred can be configured to execute `alfred-gitignore` for every key that a user types. This
     /// makes it possible to offer suggestions and autocomplete for their input. This method takes
-    /// the last element in the query, and matches it against the templates.
+    /// the last element in the query, and matches it against the templates and presets.
     ///
     /// If only a single match is found, it indicates that the last element is already correct and
-    /// done. In this case, all templates except the ones in the current query are returned.
-    pub fn suggestions(&self) -> Vec<String> {
+    /// done. In this case, all templates and presets are returned. Templates and presets that are
+    /// already in the query are never suggested again, and templates are compared by their path so
+    /// that a template whose name only differs in case from a selected one is still suggested.
+    ///
+    /// When the query contains a category qualifier, only templates in that category are
+    /// suggested. Qualifiers themselves are suggested while the user is typing one.
+    ///
+    /// Favorites are listed before all other suggestions.
+    pub fn suggestions(&self) -> Vec<Suggestion> {
+        let selected: Vec<String> = self
+            .sanitized_query()
+            .iter()
+            .map(|template| template.path().clone())
+            .collect();
+
+        let candidates = self
+            .templates()
+            .filter(|template| !selected.contains(template.path()))
+            .filter(|template| {
+                self.category
+                    .is_none_or(|category| template.category() == category)
+            })
+            .map(|template| {
+                let location = if self.namesakes(template) > 1 {
+                    Some(template.path().as_str())
+                } else {
+                    None
+                };
+
+                let mut suggestion =
+                    Suggestion::from_template(template, self.address(template), location);
+                suggestion.favorite = self.favorites.contains(template.qualified_name());
+
+                suggestion
+            })
+            .chain(
+                self.presets_map
+                    .values()
+                    .filter(|preset| !self.query_map.contains_key(preset.comparator()))
+                    .filter(|_preset| self.category.is_none())
+                    .map(Suggestion::from_preset),
+            );
+
         let last_element = self.query.last();
 
-        let mut suggestions: Vec<String> = if let Some(last_element) = last_element {
-            let last_element = last_element.to_lowercase();
+        let mut suggestions: Vec<Suggestion> = if let Some(last_element) = last_element {
+            if last_element.to_lowercase().starts_with(CATEGORY_PREFIX)
+                && qualifier(last_element).is_none()
+            {
+                let last_element = last_element.to_lowercase();
 
-            if self.templates_map.contains_key(last_element.as_str()) {
-                self.templates_map
+                Category::ALL
                     .iter()
-                    .filter(|(key, _template)| !self.query_map.contains_key(*key))
-                    .map(|(_key, template)| template.name())
-                    .cloned()
+                    .copied()
+                    .map(Suggestion::from_category)
+                    .filter(|suggestion| suggestion.starts_with(last_element.as_str()))
                     .collect()
+            } else if last_element.starts_with(EXCLUSION_PREFIX)
+                || qualifier(last_element).is_some()
+                || self.is_known(last_element)
+            {
+                candidates.collect()
             } else {
-                self.templates_map
-                    .iter()
-                    .filter(|(_key, template)| {
-                        template.comparator().starts_with(last_element.as_str())
-                    })
-                    .map(|(_key, template)| template.name())
-                    .cloned()
+                let last_element = last_element.to_lowercase();
+
+                candidates
+                    .filter(|suggestion| suggestion.starts_with(last_element.as_str()))
                     .collect()
             }
         } else {
-            self.templates_map
-                .values()
-                .map(|template| template.name())
-                .cloned()
-                .collect()
+            candidates.collect()
         };
 
-        suggestions.sort();
+        suggestions.sort_by(|a, b| {
+            b.favorite
+                .cmp(&a.favorite)
+                .then_with(|| a.name().cmp(b.name()))
+                .then_with(|| a.address().cmp(b.address()))
+        });
         suggestions
     }
 }
 
+/// Returns the category of a qualifier like `cat:editor`, or `None` if the input is not one.
+fn qualifier(input: &str) -> Option<Category> {
+    let input = input.to_lowercase();
+
+    input
+        .strip_prefix(CATEGORY_PREFIX)
+        .and_then(|category| category.parse().ok())
+}
+
+/// Splits the user's input into the elements of the query.
+///
+/// Alfred passes the query to the workflow as separate arguments that are split on whitespace. An
+/// element that is wrapped in double quotes can span multiple arguments, and is joined back into a
+/// single element. A quote that is not closed yet extends to the end of the input, since the user
+/// is most likely still typing.
+pub fn tokenize(values: &[&str]) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut quoted: Option<String> = None;
+
+    for value in values {
+        if let Some(mut token) = quoted.take() {
+            token.push(' ');
+
+            match value.strip_suffix('"') {
+                Some(value) => {
+                    token.push_str(value);
+                    tokens.push(token);
+                }
+                None => {
+                    token.push_str(value);
+                    quoted = Some(token);
+                }
+            }
+        } else if let Some(value) = value.strip_prefix('"') {
+            match value.strip_suffix('"') {
+                Some(value) => tokens.push(String::from(value)),
+                None => quoted = Some(String::from(value)),
+            }
+        } else {
+            tokens.push(String::from(*value));
+        }
+    }
+
+    if let Some(token) = quoted {
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+/// Returns the number of arguments that the first element of the query spans.
+///
+/// An element in double quotes spans all arguments up to the closing quote, like in [`tokenize`].
+/// This is used for options that take a single name, since Alfred splits their value, too.
+pub fn span(values: &[&str]) -> usize {
+    let opened = matches!(
+        values.first().and_then(|value| value.strip_prefix('"')),
+        Some(value) if !value.ends_with('"')
+    );
+
+    if !opened {
+        return values.len().min(1);
+    }
+
+    values
+        .iter()
+        .skip(1)
+        .position(|value| value.ends_with('"'))
+        .map_or(values.len(), |position| position + 2)
+}
+
+/// Wraps a name in double quotes if it contains whitespace.
+pub fn quote(name: &str) -> String {
+    if name.contains(char::is_whitespace) {
+        format!("\"{name}\"")
+    } else {
+        String::from(name)
+    }
+}
+
+/// Returns the Levenshtein distance between two strings.
+///
+/// The distance is the number of characters that need to be inserted, deleted, or substituted to
+/// turn one string into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            let insertion = current[j] + 1;
+            let deletion = previous[j + 1] + 1;
+
+            current.push(substitution.min(insertion).min(deletion));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::query::Query;
-    use crate::testing::initialize_repository;
+    use crate::category::Category;
+    use crate::query::{edit_distance, quote, span, tokenize, Kind, Query, Suggestion};
+    use crate::testing::{create_case_variant, initialize_repository, write_config};
+    use std::fs::{create_dir, File};
     use tempfile::TempDir;
 
+    fn names(suggestions: Vec<Suggestion>) -> Vec<String> {
+        suggestions
+            .iter()
+            .map(|suggestion| suggestion.name())
+            .cloned()
+            .collect()
+    }
+
     #[test]
     fn new_without_query() {
         let tempdir = TempDir::new().unwrap();
@@ -148,6 +748,224 @@ mod tests {
         assert_eq!(&String::from("apples"), query.first().unwrap().name());
     }
 
+    #[test]
+    fn sanitized_query_without_duplicates() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+
+        let query = Query::new(
+            &repository,
+            Some(vec!["oranges", "Apples", "apples", "Oranges"]),
+        )
+        .unwrap();
+        let names: Vec<String> = query
+            .sanitized_query()
+            .iter()
+            .map(|template| template.name())
+            .cloned()
+            .collect();
+
+        assert_eq!(vec!["oranges", "apples"], names);
+        assert_eq!(2, query.duplicates().len());
+    }
+
+    #[test]
+    fn unresolved() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+
+        let query = Query::new(&repository, Some(vec!["aples", "Apples", "Peaches"])).unwrap();
+
+        assert_eq!(vec!["aples", "Peaches"], query.unresolved());
+    }
+
+    #[test]
+    fn is_incomplete() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+
+        let incomplete = Query::new(&repository, Some(vec!["app"])).unwrap();
+        let complete = Query::new(&repository, Some(vec!["apples"])).unwrap();
+        let typo = Query::new(&repository, Some(vec!["aples"])).unwrap();
+
+        assert!(incomplete.is_incomplete());
+        assert!(!complete.is_incomplete());
+        assert!(!typo.is_incomplete());
+    }
+
+    #[test]
+    fn corrections() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+
+        let query = Query::new(&repository, None).unwrap();
+
+        assert_eq!(vec!["apples"], query.corrections("Aples"));
+        assert_eq!(vec!["oranges"], query.corrections("ornages"));
+        assert!(query.corrections("peaches").is_empty());
+    }
+
+    #[test]
+    fn edit_distance_between_strings() {
+        assert_eq!(0, edit_distance("apples", "apples"));
+        assert_eq!(1, edit_distance("aples", "apples"));
+        assert_eq!(3, edit_distance("kitten", "sitting"));
+        assert_eq!(6, edit_distance("", "apples"));
+    }
+
+    #[test]
+    fn sanitized_query_with_qualified_names() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        create_dir(tempdir.path().join("Global")).unwrap();
+        File::create(tempdir.path().join("Global/apples.gitignore")).unwrap();
+        File::create(tempdir.path().join("Global/Visual Studio.gitignore")).unwrap();
+
+        let query = Query::new(
+            &repository,
+            Some(vec!["apples", "global/Apples", "\"visual", "studio\""]),
+        )
+        .unwrap();
+        let names: Vec<String> = query
+            .sanitized_query()
+            .iter()
+            .map(|template| template.qualified_name())
+            .cloned()
+            .collect();
+
+        assert_eq!(
+            vec!["apples", "Global/apples", "Global/Visual Studio"],
+            names
+        );
+    }
+
+    #[test]
+    fn address() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        create_dir(tempdir.path().join("Global")).unwrap();
+        File::create(tempdir.path().join("Global/apples.gitignore")).unwrap();
+        File::create(tempdir.path().join("Global/Visual Studio.gitignore")).unwrap();
+
+        let query = Query::new(&repository, Some(vec!["global/apples", "visual studio"])).unwrap();
+        let addresses: Vec<String> = query
+            .sanitized_query()
+            .iter()
+            .map(|template| query.address(template))
+            .collect();
+
+        assert_eq!(vec!["Global/apples", "\"Visual Studio\""], addresses);
+    }
+
+    #[test]
+    fn tokenize_quoted_names() {
+        assert_eq!(vec!["apples"], tokenize(&["apples"]));
+        assert_eq!(vec!["Visual Studio"], tokenize(&["\"Visual", "Studio\""]));
+        assert_eq!(vec!["Visual Studio"], tokenize(&["Visual Studio"]));
+        assert_eq!(vec!["apples", "Visual"], tokenize(&["apples", "\"Visual"]));
+        assert_eq!(vec!["apples"], tokenize(&["\"apples\""]));
+    }
+
+    #[test]
+    fn span_quoted_names() {
+        assert_eq!(0, span(&[]));
+        assert_eq!(1, span(&["apples", "oranges"]));
+        assert_eq!(1, span(&["\"Visual Studio\"", "apples"]));
+        assert_eq!(2, span(&["\"Visual", "Studio\"", "apples"]));
+        assert_eq!(2, span(&["\"Visual", "Studio"]));
+    }
+
+    #[test]
+    fn quote_names() {
+        assert_eq!("apples", quote("apples"));
+        assert_eq!("\"Visual Studio\"", quote("Visual Studio"));
+    }
+
+    #[test]
+    fn sanitized_query_with_names_that_differ_in_case() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        if !create_case_variant(&repository).unwrap() {
+            return;
+        }
+
+        let query = Query::new(&repository, Some(vec!["Apples", "apples", "APPLES"])).unwrap();
+        let paths: Vec<String> = query
+            .sanitized_query()
+            .iter()
+            .map(|template| template.path())
+            .cloned()
+            .collect();
+
+        assert_eq!(vec!["Apples.gitignore", "apples.gitignore"], paths);
+        assert_eq!(2, query.templates_map.get("apples").unwrap().len());
+    }
+
+    #[test]
+    fn suggestions_with_names_that_differ_in_case() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        if !create_case_variant(&repository).unwrap() {
+            return;
+        }
+
+        let query = Query::new(&repository, Some(vec!["app"])).unwrap();
+        let suggestions = query.suggestions();
+        let addresses: Vec<&String> = suggestions
+            .iter()
+            .map(|suggestion| suggestion.address())
+            .collect();
+        let descriptions: Vec<Option<String>> = suggestions
+            .iter()
+            .map(|suggestion| suggestion.description().clone())
+            .collect();
+
+        assert_eq!(vec!["Apples", "apples"], addresses);
+        assert_eq!(
+            vec![
+                Some(String::from("Apples.gitignore")),
+                Some(String::from("apples.gitignore: This file ignores apples"))
+            ],
+            descriptions
+        );
+    }
+
+    #[test]
+    fn exclusions() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        write_config(&repository, "preset.fruits = apples -/apples oranges\n").unwrap();
+
+        let query = Query::new(
+            &repository,
+            Some(vec![
+                "-/peaches",
+                "oranges",
+                "-/oranges",
+                "-",
+                "fruits",
+                "-*.log",
+            ]),
+        )
+        .unwrap();
+        let exclusions = query.exclusions();
+
+        let apples = exclusions.get("apples.gitignore").unwrap();
+        let oranges = exclusions.get("oranges.gitignore").unwrap();
+        let patterns: Vec<&String> = oranges.iter().map(|e| e.pattern()).collect();
+
+        assert_eq!(2, query.sanitized_query().len());
+        assert_eq!(2, apples.len());
+        assert_eq!("/apples", apples.first().unwrap().pattern());
+        assert_eq!(
+            "Excluded by preset fruits",
+            apples.first().unwrap().reason()
+        );
+        assert_eq!(vec!["/oranges", "*.log"], patterns);
+        assert_eq!("Excluded by the query", oranges.first().unwrap().reason());
+        assert_eq!(vec!["-/peaches"], query.unmatched_exclusions());
+    }
+
     #[test]
     fn suggestions_without_query() {
         let tempdir = TempDir::new().unwrap();
@@ -157,7 +975,11 @@ mod tests {
         let suggestions = query.suggestions();
 
         assert_eq!(2, suggestions.len());
-        assert_eq!(vec!["apples", "oranges"], suggestions);
+        assert_eq!(
+            &Some(String::from("This file ignores apples")),
+            suggestions.first().unwrap().description()
+        );
+        assert_eq!(vec!["apples", "oranges"], names(suggestions));
     }
 
     #[test]
@@ -169,7 +991,7 @@ mod tests {
         let suggestions = query.suggestions();
 
         assert_eq!(1, suggestions.len());
-        assert_eq!(vec!["oranges"], suggestions);
+        assert_eq!(vec!["oranges"], names(suggestions));
     }
 
     #[test]
@@ -181,6 +1003,151 @@ mod tests {
         let suggestions = query.suggestions();
 
         assert_eq!(1, suggestions.len());
-        assert_eq!(vec!["oranges"], suggestions);
+        assert_eq!(vec!["oranges"], names(suggestions));
+    }
+
+    #[test]
+    fn sanitized_query_expands_presets() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        write_config(&repository, "preset.Fruits = oranges apples peaches\n").unwrap();
+
+        let query = Query::new(&repository, Some(vec!["fruits"])).unwrap();
+        let templates = query.sanitized_query();
+        let names: Vec<&String> = templates.iter().map(|template| template.name()).collect();
+
+        assert_eq!(vec!["oranges", "apples"], names);
+        assert_eq!(vec!["peaches"], query.unresolved());
+    }
+
+    #[test]
+    fn suggestions_with_preset() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        write_config(&repository, "preset.Fruits = apples oranges\n").unwrap();
+
+        let query = Query::new(&repository, Some(vec!["f"])).unwrap();
+        let suggestions = query.suggestions();
+        let suggestion = suggestions.first().unwrap();
+
+        assert_eq!(1, suggestions.len());
+        assert_eq!("Fruits", suggestion.name());
+        assert_eq!(Kind::Preset, suggestion.kind());
+        assert_eq!(
+            &Some(String::from("Preset: apples, oranges")),
+            suggestion.description()
+        );
+    }
+
+    #[test]
+    fn suggestions_in_category() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        create_dir(repository.path().join("Global")).unwrap();
+        File::create(repository.path().join("Global/macOS.gitignore")).unwrap();
+        File::create(repository.path().join("Global/JetBrains.gitignore")).unwrap();
+        File::create(repository.path().join("Global/Archives.gitignore")).unwrap();
+        write_config(&repository, "preset.Fruits = apples oranges\n").unwrap();
+
+        let query = Query::new(&repository, Some(vec!["cat:editor"])).unwrap();
+
+        assert_eq!(Some(Category::Editor), query.category());
+        assert!(query.unresolved().is_empty());
+        assert!(!query.is_incomplete());
+        assert_eq!(vec!["JetBrains"], names(query.suggestions()));
+    }
+
+    #[test]
+    fn suggestions_in_category_with_prefix() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        create_dir(repository.path().join("Global")).unwrap();
+        File::create(repository.path().join("Global/macOS.gitignore")).unwrap();
+        File::create(repository.path().join("Global/Mercurial.gitignore")).unwrap();
+
+        let query = Query::new(&repository, Some(vec!["cat:os", "m"])).unwrap();
+
+        assert_eq!(vec!["macOS"], names(query.suggestions()));
+    }
+
+    #[test]
+    fn suggestions_for_incomplete_qualifier() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+
+        let query = Query::new(&repository, Some(vec!["apples", "cat:e"])).unwrap();
+        let suggestions = query.suggestions();
+
+        assert!(query.is_incomplete());
+        assert_eq!(None, query.category());
+        assert_eq!(Kind::Category, suggestions.first().unwrap().kind());
+        assert_eq!(vec!["cat:editor"], names(suggestions));
+    }
+
+    #[test]
+    fn suggestions_with_favorites() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        let mut favorites = repository.favorites().unwrap();
+        favorites.toggle("oranges");
+        favorites.write(repository.path()).unwrap();
+
+        let query = Query::new(&repository, None).unwrap();
+        let suggestions = query.suggestions();
+
+        assert!(suggestions.first().unwrap().favorite());
+        assert!(!suggestions.get(1).unwrap().favorite());
+        assert_eq!(vec!["oranges", "apples"], names(suggestions));
+    }
+
+    #[test]
+    fn suggestions_exclude_selected_templates_while_typing() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        File::create(tempdir.path().join("apricots.gitignore")).unwrap();
+
+        let query = Query::new(&repository, Some(vec!["Apples", "ap"])).unwrap();
+
+        assert_eq!(vec!["apricots"], names(query.suggestions()));
+    }
+
+    #[test]
+    fn suggestions_exclude_selected_presets_while_typing() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        write_config(
+            &repository,
+            "preset.Fruits = apples\npreset.Fries = oranges\n",
+        )
+        .unwrap();
+
+        let query = Query::new(&repository, Some(vec!["fruits", "fr"])).unwrap();
+
+        assert_eq!(vec!["Fries"], names(query.suggestions()));
+    }
+
+    #[test]
+    fn suggestions_exclude_selected_case_variants() {
+        let tempdir = TempDir::new().unwrap();
+        let repository = initialize_repository(tempdir.path()).unwrap();
+        if !create_case_variant(&repository).unwrap() {
+            return;
+        }
+
+        let typing = Query::new(&repository, Some(vec!["apples", "app"])).unwrap();
+        let complete = Query::new(&repository, Some(vec!["Apples"])).unwrap();
+        let both = Query::new(&repository, Some(vec!["Apples", "apples", "a"])).unwrap();
+
+        let addresses = |query: &Query| -> Vec<String> {
+            query
+                .suggestions()
+                .iter()
+                .map(|suggestion| suggestion.address().clone())
+                .collect()
+        };
+
+        assert_eq!(vec!["Apples"], addresses(&typing));
+        assert_eq!(vec!["apples", "oranges"], addresses(&complete));
+        assert!(addresses(&both).is_empty());
     }
 }
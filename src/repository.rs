@@ -1,38 +1,127 @@
-use getset::Getters;
+use crate::category::Category;
+use crate::config::Config;
+use crate::favorites::Favorites;
+use crate::history::History;
+use crate::index::Index;
+use getset::{CopyGetters, Getters, Setters};
 use std::env::temp_dir;
-use std::fs::{create_dir, read_dir, File};
+use std::fs::{create_dir, create_dir_all, read_to_string, remove_file, write, File};
 use std::io::{copy, Error, ErrorKind};
 use std::path::{Path, PathBuf};
 use zip::ZipArchive;
 
 const ARCHIVE: &str = "https://github.com/github/gitignore/archive/main.zip";
 
+/// The name of the file that stores the upstream revision of the templates.
+const REVISION_FILE: &str = "revision";
+
 /// A template represents a `*.gitignore` file in a repository.
-#[derive(Clone, Debug, Getters)]
+#[derive(Clone, Debug, Getters, CopyGetters, Setters)]
 pub struct Template {
     /// Returns the name of the template.
     #[getset(get = "pub")]
     name: String,
 
+    /// Returns the name of the template including the directories it is in.
+    #[getset(get = "pub")]
+    qualified_name: String,
+
     /// Returns the file name of the template.
     #[getset(get = "pub")]
     file_name: String,
 
+    /// Returns the path to the template relative to the repository.
+    #[getset(get = "pub")]
+    path: String,
+
     /// Returns a sanitized version of the template's name for comparisons.
     #[getset(get = "pub")]
     comparator: String,
+
+    /// Returns a short description of the template.
+    #[getset(get = "pub", set = "pub")]
+    description: Option<String>,
+
+    /// Returns keywords that describe the template.
+    #[getset(get = "pub", set = "pub")]
+    tags: Vec<String>,
+
+    /// Returns the category of the template.
+    #[getset(get_copy = "pub", set = "pub")]
+    category: Category,
 }
 
 impl Template {
-    /// Returns a new instance of a template for the given file name.
-    pub fn new(file_name: &str) -> Template {
-        let name = file_name.replace(".gitignore", "");
+    /// Returns a new instance of a template for the given path.
+    ///
+    /// The path is relative to the repository, and uses `/` to separate directories. Templates at
+    /// the root of the repository are addressed by their name, e.g. `Rust`, while templates in
+    /// subdirectories have a qualified name that includes the directories, e.g. `Global/macOS`.
+    ///
+    /// The category and tags of the template are derived from its path. The description is read
+    /// from the template's content, and must be set separately.
+    pub fn new(path: &str) -> Template {
+        let qualified_name = path.trim_end_matches(".gitignore");
+        let (directories, name) = match qualified_name.rsplit_once('/') {
+            Some((directories, name)) => (directories, name),
+            None => ("", qualified_name),
+        };
+        let category = Category::of(qualified_name);
+
+        let mut tags = vec![category.to_string()];
+        let words = directories
+            .split('/')
+            .filter(|directory| !directory.is_empty())
+            .map(str::to_lowercase)
+            .chain(Template::words(name));
+        for word in words {
+            if !tags.contains(&word) {
+                tags.push(word);
+            }
+        }
 
         Template {
+            name: String::from(name),
+            qualified_name: String::from(qualified_name),
             comparator: name.to_lowercase(),
-            file_name: String::from(file_name),
-            name,
+            file_name: format!("{name}.gitignore"),
+            path: String::from(path),
+            description: None,
+            tags,
+            category,
+        }
+    }
+
+    /// Splits a name into its lowercase words.
+    ///
+    /// Most templates are named in camel case, so a new word starts whenever a lowercase letter is
+    /// followed by an uppercase letter. For example, `VisualStudioCode` is split into `visual`,
+    /// `studio`, and `code`.
+    fn words(name: &str) -> Vec<String> {
+        let mut words = Vec::new();
+        let mut word = String::new();
+        let mut previous: Option<char> = None;
+
+        for c in name.chars() {
+            let boundary = previous.is_some_and(|p| p.is_lowercase() && c.is_uppercase());
+
+            if (!c.is_alphanumeric() || boundary) && !word.is_empty() {
+                words.push(word.to_lowercase());
+                word.clear();
+            }
+
+            if c.is_alphanumeric() {
+                word.push(c);
+            }
+
+            previous = Some(c);
+        }
+
+        if !word.is_empty() {
+            words.push(word.to_lowercase());
         }
+
+        words
     }
 }
 
@@ -41,7 +130,7 @@ impl Template {
 /// The repository is a copy of the [github/gitignore](https://github.com/github/gitignore) Git
 /// repository, and contains all `.gitignore` files. A local copy of the remote repository is
 /// maintained on disk to speed up searches and to provide offline capabilities.
-#[derive(Debug, Getters)]
+#[derive(Clone, Debug, Getters)]
 pub struct Repository {
     /// Returns the path to the repository.
     #[getset(get = "pub")]
@@ -62,38 +151,66 @@ impl Repository {
         Ok(Repository { path })
     }
 
+    /// Returns the user's configuration.
+    ///
+    /// The configuration is stored next to the templates in the repository, since both live in the
+    /// workflow data directory.
+    pub fn config(&self) -> Result<Config, Error> {
+        Config::load(self.path())
+    }
+
+    /// Returns the templates that the user has marked as favorites.
+    pub fn favorites(&self) -> Result<Favorites, Error> {
+        Favorites::load(self.path())
+    }
+
+    /// Returns the history of past builds.
+    pub fn history(&self) -> Result<History, Error> {
+        History::load(self.path())
+    }
+
+    /// Returns the commit of github/gitignore that the templates were downloaded from.
+    ///
+    /// The revision is only known after the templates have been updated at least once.
+    pub fn revision(&self) -> Result<Option<String>, Error> {
+        let path = self.path().join(REVISION_FILE);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let revision = read_to_string(path)?;
+        let revision = revision.trim();
+
+        Ok((!revision.is_empty()).then(|| String::from(revision)))
+    }
+
     /// Returns a list of templates in the repository.
     ///
     /// The templates in a repository are all the `*.gitignore` files in its path. Since this is a
     /// convention, only the base names of the files are returned without their ending. For example,
     /// only `GitHub` is returned for the template `GitHub.gitignore`.
+    ///
+    /// The templates are read from the repository's index, which is rebuilt first if it is missing
+    /// or outdated.
     pub fn templates(&self) -> Result<Vec<Template>, Error> {
-        let entries = read_dir(self.path())?;
-
-        let mut templates: Vec<Template> = entries
-            .filter_map(|entry| {
-                let entry = match entry {
-                    Ok(entry) => entry,
-                    Err(_) => return None,
-                };
-
-                let file_name = entry.file_name();
-                let file_name = match file_name.to_str() {
-                    Some(file_name) => file_name,
-                    None => return None,
-                };
-
-                if file_name.ends_with(".gitignore") {
-                    Some(Template::new(file_name))
-                } else {
-                    None
-                }
-            })
-            .collect();
+        Ok(self.index()?.templates())
+    }
+
+    /// Returns the index of the templates in the repository.
+    pub fn index(&self) -> Result<Index, Error> {
+        match Index::load(self.path())? {
+            Some(index) => Ok(index),
+            None => self.rebuild_index(),
+        }
+    }
 
-        templates.sort_by(|a, b| a.name().cmp(b.name()));
+    /// Rebuilds the index of the templates in the repository and writes it to disk.
+    fn rebuild_index(&self) -> Result<Index, Error> {
+        let index = Index::build(self.path())?;
+        index.write(self.path())?;
 
-        Ok(templates)
+        Ok(index)
     }
 
     /// Update a repository with the latest templates from GitHub.
@@ -102,7 +219,25 @@ impl Repository {
     /// the repository. This operation thus requires an internet connection.
     pub fn update(&self) -> Result<(), Error> {
         let archive = self.download_archive(None)?;
+        self.remove_templates()?;
         self.extract_archive(&archive)?;
+        self.rebuild_index()?;
+
+        Ok(())
+    }
+
+    /// Removes the templates from the repository.
+    ///
+    /// Templates are removed before the latest templates are extracted, so that templates that
+    /// were renamed, moved, or deleted upstream do not linger in the repository.
+    fn remove_templates(&self) -> Result<(), Error> {
+        for template in self.index()?.templates() {
+            let path = self.path().join(template.path());
+
+            if path.exists() {
+                remove_file(path)?;
+            }
+        }
 
         Ok(())
     }
@@ -133,9 +268,12 @@ impl Repository {
     /// Extract an archive and write its files into a repository.
     ///
     /// An archive is a `.zip` file that contains many `.gitignore` files. When extracting it, these
-    /// files are written to the given repository. While doing so, the directory structure is
-    /// flattened. For example, `github/gitignore.gitignore` is written to `gitignore.gitignore` and
-    /// the `github` directory is ignored.
+    /// files are written to the given repository. While doing so, the top-level directory of the
+    /// archive is removed, but all other directories are kept. For example,
+    /// `gitignore-main/Global/macOS.gitignore` is written to `Global/macOS.gitignore`.
+    ///
+    /// GitHub stores the commit of the archive in its comment, which is written to the repository
+    /// as its revision.
     fn extract_archive(&self, archive: &Path) -> Result<(), Error> {
         let file = File::open(archive)?;
 
@@ -149,26 +287,36 @@ impl Repository {
             }
         };
 
+        let revision = String::from_utf8_lossy(archive.comment())
+            .trim()
+            .to_string();
+        write(self.path().join(REVISION_FILE), revision)?;
+
         for i in 0..archive.len() {
             let mut file = match archive.by_index(i) {
                 Ok(file) => file,
                 Err(_) => continue, // TODO Log an error
             };
 
-            let sanitized_name = PathBuf::from(file.name());
-
-            let file_name = match sanitized_name.file_name() {
-                Some(base_name) => base_name,
+            let enclosed_name = match file.enclosed_name() {
+                Some(enclosed_name) => enclosed_name.to_path_buf(),
                 None => continue, // TODO Log an error
             };
 
-            let file_name_as_str = match file_name.to_str() {
+            let relative_path: PathBuf = enclosed_name.components().skip(1).collect();
+
+            let file_name_as_str = match relative_path.file_name().and_then(|name| name.to_str()) {
                 Some(name) => name,
                 None => continue, // TODO Log an error
             };
 
-            if file_name_as_str.ends_with("gitignore") {
-                let file_path = self.path().join(file_name_as_str);
+            if file_name_as_str.ends_with(".gitignore") && file_name_as_str != ".gitignore" {
+                let file_path = self.path().join(&relative_path);
+
+                if let Some(parent) = file_path.parent() {
+                    create_dir_all(parent)?;
+                }
+
                 let mut destination = File::create(file_path)?;
 
                 copy(&mut file, &mut destination)?;
@@ -181,7 +329,7 @@ impl Repository {
 
 #[cfg(test)]
 mod tests {
-    use crate::repository::Repository;
+    use crate::repository::{Repository, Template};
     use crate::testing::initialize_repository;
     use mockito::{Server, ServerGuard};
     use std::fs::{remove_file, File};
@@ -251,8 +399,23 @@ mod tests {
 
         repository.extract_archive(&archive_path).unwrap();
 
-        let gitignore_count = repository.templates().unwrap().len();
-        assert_eq!(229, gitignore_count);
+        let templates = repository.templates().unwrap();
+        assert_eq!(229, templates.len());
+        assert_eq!(
+            Some(String::from("2a4de265d37eca626309d8e115218d18985b5435")),
+            repository.revision().unwrap()
+        );
+        assert!(templates
+            .iter()
+            .any(|template| template.qualified_name() == "Global/macOS"));
+    }
+
+    #[test]
+    fn revision_before_update() {
+        let repository_path = TempDir::new().unwrap();
+        let repository = initialize_repository(repository_path.path()).unwrap();
+
+        assert_eq!(None, repository.revision().unwrap());
     }
 
     #[test]
@@ -265,4 +428,37 @@ mod tests {
 
         assert_eq!(vec!["apples", "oranges"], template_names);
     }
+
+    #[test]
+    fn templates_writes_index() {
+        let repository_path = TempDir::new().unwrap();
+        let repository = initialize_repository(repository_path.path()).unwrap();
+
+        repository.templates().unwrap();
+
+        assert!(repository.path().join("index").exists());
+    }
+
+    #[test]
+    fn template_at_root() {
+        let template = Template::new("VisualStudioCode.gitignore");
+
+        assert_eq!("VisualStudioCode", template.name());
+        assert_eq!("VisualStudioCode", template.qualified_name());
+        assert_eq!(&vec!["editor", "visual", "studio", "code"], template.tags());
+    }
+
+    #[test]
+    fn template_in_directory() {
+        let template = Template::new("community/Java/JBoss4.gitignore");
+
+        assert_eq!("JBoss4", template.name());
+        assert_eq!("community/Java/JBoss4", template.qualified_name());
+        assert_eq!("JBoss4.gitignore", template.file_name());
+        assert_eq!("community/Java/JBoss4.gitignore", template.path());
+        assert_eq!(
+            &vec!["framework", "community", "java", "jboss4"],
+            template.tags()
+        );
+    }
 }
@@ -0,0 +1,392 @@
+use getset::Getters;
+use std::io::{Error, ErrorKind};
+
+/// The beginning of the line that opens a managed section.
+const START_PREFIX: &str = "# >>> alfred-gitignore: ";
+
+/// The end of the line that opens a managed section.
+const START_SUFFIX: &str = " >>>";
+
+/// The beginning of the line that closes a managed section.
+const END_PREFIX: &str = "# <<< alfred-gitignore: ";
+
+/// The end of the line that closes a managed section.
+const END_SUFFIX: &str = " <<<";
+
+/// The beginning of a comment that records an excluded pattern.
+const EXCLUSION_PREFIX: &str = "# Excluded by ";
+
+/// A section of a `.gitignore` file that is managed by the workflow.
+///
+/// Each template is written to its own section, which is delimited by markers that contain the
+/// name of the section. The markers make it possible to find the sections in an existing file and
+/// update them, without touching the lines that the user has added by hand:
+///
+/// ```text
+/// # >>> alfred-gitignore: Global/macOS >>>
+/// ### macOS.gitignore
+/// .DS_Store
+/// # <<< alfred-gitignore: Global/macOS <<<
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Getters)]
+pub struct Section {
+    /// Returns the name of the section, which is the qualified name of its template.
+    #[getset(get = "pub")]
+    name: String,
+
+    /// Returns the heading of the section, which is the file name of its template.
+    #[getset(get = "pub")]
+    heading: String,
+
+    /// Returns the patterns and comments in the section.
+    #[getset(get = "pub")]
+    content: String,
+}
+
+impl Section {
+    /// Returns a new section with the given name, heading, and content.
+    pub fn new(name: &str, heading: &str, content: String) -> Self {
+        Section {
+            name: String::from(name),
+            heading: String::from(heading),
+            content,
+        }
+    }
+
+    /// Replaces the content of the section.
+    pub fn set_content(&mut self, content: String) {
+        self.content = content;
+    }
+
+    /// Returns the patterns that were excluded from the section's template.
+    ///
+    /// Excluded patterns are commented out with the reason for their exclusion, for example
+    /// `# Excluded by the query: dist/`, which makes it possible to exclude them again when the
+    /// section is rebuilt.
+    pub fn exclusions(&self) -> Vec<String> {
+        self.content
+            .lines()
+            .filter_map(|line| line.strip_prefix(EXCLUSION_PREFIX))
+            .filter_map(|rest| rest.split_once(": "))
+            .map(|(_reason, pattern)| String::from(pattern.trim()))
+            .collect()
+    }
+
+    /// Returns the number of lines that were added and removed in the updated section.
+    ///
+    /// Lines are compared regardless of their position, which is enough to tell whether and how
+    /// much a template has changed.
+    pub fn changes(&self, updated: &Section) -> (usize, usize) {
+        let mut removed: Vec<&str> = self.content.lines().collect();
+        let mut added = 0;
+
+        for line in updated.content.lines() {
+            match removed.iter().position(|old| *old == line) {
+                Some(position) => {
+                    removed.remove(position);
+                }
+                None => added += 1,
+            }
+        }
+
+        (added, removed.len())
+    }
+
+    /// Returns the section without comments and blank lines, and with only its heading.
+    ///
+    /// Only lines that start with `#` are comments, so patterns with an escaped `\#` are kept.
+    /// Without markers, a minified section cannot be found and updated later.
+    pub fn minify(&self) -> String {
+        let mut minified = format!("### {}\n", self.heading);
+
+        for line in self.content.lines() {
+            if !line.trim().is_empty() && !line.starts_with('#') {
+                minified.push_str(line);
+                minified.push('\n');
+            }
+        }
+
+        minified
+    }
+
+    /// Returns the section with its markers and heading.
+    pub fn render(&self) -> String {
+        let mut rendered = format!("{START_PREFIX}{}{START_SUFFIX}\n", self.name);
+        rendered.push_str(&format!("### {}\n", self.heading));
+        rendered.push_str(&self.content);
+
+        if !self.content.is_empty() && !self.content.ends_with('\n') {
+            rendered.push('\n');
+        }
+
+        rendered.push_str(&format!("{END_PREFIX}{}{END_SUFFIX}\n", self.name));
+        rendered
+    }
+}
+
+/// A block of lines in a `.gitignore` file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Block {
+    /// Lines that the user wrote by hand.
+    Text(String),
+
+    /// A section that is managed by the workflow.
+    Section(Section),
+}
+
+/// A `.gitignore` file that consists of hand-written lines and managed sections.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Getters)]
+pub struct Document {
+    /// Returns the blocks of the document in the order in which they appear in the file.
+    #[getset(get = "pub")]
+    blocks: Vec<Block>,
+}
+
+impl Document {
+    /// Parses the content of a `.gitignore` file.
+    ///
+    /// An error is returned if a managed section is not closed, since the end of the section and
+    /// thus the lines that belong to the user cannot be determined.
+    pub fn parse(content: &str) -> Result<Self, Error> {
+        let mut blocks = Vec::new();
+        let mut text = String::new();
+        let mut lines = content.split_inclusive('\n');
+
+        while let Some(line) = lines.next() {
+            let name = match line
+                .trim_end()
+                .strip_prefix(START_PREFIX)
+                .and_then(|rest| rest.strip_suffix(START_SUFFIX))
+            {
+                Some(name) => name,
+                None => {
+                    text.push_str(line);
+                    continue;
+                }
+            };
+
+            if !text.is_empty() {
+                blocks.push(Block::Text(text));
+                text = String::new();
+            }
+
+            let end = format!("{END_PREFIX}{name}{END_SUFFIX}");
+            let mut heading = String::new();
+            let mut content = String::new();
+            let mut closed = false;
+
+            for line in lines.by_ref() {
+                if line.trim_end() == end {
+                    closed = true;
+                    break;
+                }
+
+                match line.trim_end().strip_prefix("### ") {
+                    Some(title) if heading.is_empty() && content.is_empty() => {
+                        heading = String::from(title)
+                    }
+                    _ => content.push_str(line),
+                }
+            }
+
+            if !closed {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("The section '{name}' in the .gitignore file is not closed"),
+                ));
+            }
+
+            blocks.push(Block::Section(Section::new(name, &heading, content)));
+        }
+
+        if !text.is_empty() {
+            blocks.push(Block::Text(text));
+        }
+
+        Ok(Document { blocks })
+    }
+
+    /// Returns the managed sections in the document.
+    pub fn sections(&self) -> Vec<&Section> {
+        self.blocks
+            .iter()
+            .filter_map(|block| match block {
+                Block::Section(section) => Some(section),
+                Block::Text(_) => None,
+            })
+            .collect()
+    }
+
+    /// Merges the given sections into the document.
+    ///
+    /// Sections that already exist in the document are replaced in place, and new sections are
+    /// appended to the end. All other blocks are kept as they are, including sections of templates
+    /// that are not given.
+    pub fn merge(&mut self, sections: Vec<Section>) {
+        for section in sections {
+            let existing = self.blocks.iter_mut().find(|block| match block {
+                Block::Section(existing) => existing.name == section.name,
+                Block::Text(_) => false,
+            });
+
+            match existing {
+                Some(block) => *block = Block::Section(section),
+                None => {
+                    if !self.blocks.is_empty() {
+                        self.blocks.push(Block::Text(String::from("\n")));
+                    }
+
+                    self.blocks.push(Block::Section(section));
+                }
+            }
+        }
+    }
+
+    /// Returns the content of the document.
+    pub fn render(&self) -> String {
+        let mut rendered = String::new();
+
+        for block in &self.blocks {
+            if !rendered.is_empty() && !rendered.ends_with('\n') {
+                rendered.push('\n');
+            }
+
+            match block {
+                Block::Text(text) => rendered.push_str(text),
+                Block::Section(section) => rendered.push_str(&section.render()),
+            }
+        }
+
+        rendered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::section::{Block, Document, Section};
+
+    const DOCUMENT: &str = "\
+/target
+# >>> alfred-gitignore: Rust >>>
+### Rust.gitignore
+/target/
+# <<< alfred-gitignore: Rust <<<
+
+# Added by hand
+.env
+";
+
+    #[test]
+    fn render_section() {
+        let section = Section::new("Global/macOS", "macOS.gitignore", String::from(".DS_Store"));
+
+        assert_eq!(
+            "# >>> alfred-gitignore: Global/macOS >>>\n### macOS.gitignore\n.DS_Store\n# <<< alfred-gitignore: Global/macOS <<<\n",
+            section.render()
+        );
+    }
+
+    #[test]
+    fn minify_section() {
+        let section = Section::new(
+            "Node",
+            "Node.gitignore",
+            String::from("# Logs\nlogs\n\n\\#file\n# Excluded by the query: dist/\n*.log"),
+        );
+
+        assert_eq!(
+            "### Node.gitignore\nlogs\n\\#file\n*.log\n",
+            section.minify()
+        );
+    }
+
+    #[test]
+    fn parse_document() {
+        let document = Document::parse(DOCUMENT).unwrap();
+        let section = match document.blocks().get(1) {
+            Some(Block::Section(section)) => section,
+            _ => panic!("expected a managed section"),
+        };
+
+        assert_eq!(3, document.blocks().len());
+        assert_eq!("Rust", section.name());
+        assert_eq!("Rust.gitignore", section.heading());
+        assert_eq!("/target/\n", section.content());
+        assert_eq!(DOCUMENT, document.render());
+    }
+
+    #[test]
+    fn parse_unclosed_section() {
+        let error = Document::parse("# >>> alfred-gitignore: Rust >>>\n/target/\n").unwrap_err();
+
+        assert_eq!(
+            "The section 'Rust' in the .gitignore file is not closed",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn merge_replaces_and_appends_sections() {
+        let mut document = Document::parse(DOCUMENT).unwrap();
+
+        document.merge(vec![
+            Section::new(
+                "Rust",
+                "Rust.gitignore",
+                String::from("/target/\n**/*.rs.bk\n"),
+            ),
+            Section::new(
+                "Global/macOS",
+                "macOS.gitignore",
+                String::from(".DS_Store\n"),
+            ),
+        ]);
+
+        let rendered = document.render();
+
+        assert!(rendered.starts_with("/target\n# >>> alfred-gitignore: Rust >>>\n"));
+        assert!(rendered.contains("/target/\n**/*.rs.bk\n# <<< alfred-gitignore: Rust <<<\n"));
+        assert!(rendered.contains("# Added by hand\n.env\n\n# >>> alfred-gitignore: Global/macOS"));
+        assert!(matches!(document.blocks().last(), Some(Block::Section(_))));
+    }
+
+    #[test]
+    fn merge_into_empty_document() {
+        let mut document = Document::default();
+
+        document.merge(vec![Section::new("Rust", "Rust.gitignore", String::new())]);
+
+        assert_eq!(
+            "# >>> alfred-gitignore: Rust >>>\n### Rust.gitignore\n# <<< alfred-gitignore: Rust <<<\n",
+            document.render()
+        );
+    }
+
+    #[test]
+    fn exclusions() {
+        let section = Section::new(
+            "Node",
+            "Node.gitignore",
+            String::from("# Excluded by the query: dist/\n# Excluded by preset Web: *.log\n.env\n"),
+        );
+
+        assert_eq!(vec!["dist/", "*.log"], section.exclusions());
+    }
+
+    #[test]
+    fn changes() {
+        let old = Section::new(
+            "Rust",
+            "Rust.gitignore",
+            String::from("/target/\n*.rs.bk\n"),
+        );
+        let new = Section::new(
+            "Rust",
+            "Rust.gitignore",
+            String::from("/target/\nCargo.lock\n*.pdb\n"),
+        );
+
+        assert_eq!((2, 1), old.changes(&new));
+        assert_eq!((0, 0), old.changes(&old));
+    }
+}
@@ -22,3 +22,24 @@ pub fn initialize_repository(path: &Path) -> Result<Repository, Error> {
 
     Ok(repository)
 }
+
+/// Writes a configuration file into a repository for testing.
+pub fn write_config(repository: &Repository, content: &str) -> Result<(), Error> {
+    let mut config = File::create(repository.path().join("config"))?;
+    config.write_all(content.as_bytes())?;
+    config.sync_all()
+}
+
+/// Adds a template that differs from `apples.gitignore` only in case to a repository for testing.
+///
+/// Returns `false` without creating the template if the filesystem is case-insensitive, since the
+/// template would then replace `apples.gitignore`.
+pub fn create_case_variant(repository: &Repository) -> Result<bool, Error> {
+    let path = repository.path().join("Apples.gitignore");
+    if path.exists() {
+        return Ok(false);
+    }
+
+    File::create(path)?;
+    Ok(true)
+}
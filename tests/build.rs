@@ -1,6 +1,6 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
-use std::fs::File;
+use std::fs::{create_dir, read_to_string, write, File};
 use std::io::Write;
 use std::path::Path;
 use tempfile::TempDir;
@@ -35,3 +35,581 @@ fn build() {
         .success()
         .stdout(predicate::str::contains("### Apples.gitignore"));
 }
+
+#[test]
+fn build_in_alphabetical_order() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--order").arg("alphabetical");
+    command.arg("--build");
+    command.arg("oranges").arg("apples");
+
+    command.assert().success().stdout(predicate::str::contains(
+        r"### Apples.gitignore\n# This file ignores apples\n/apples\n# <<< alfred-gitignore: Apples <<<\n\n# >>> alfred-gitignore: Oranges >>>\n### Oranges.gitignore",
+    ));
+}
+
+#[test]
+fn build_with_extras() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--extra").arg(".env.local");
+    command.arg("--extra").arg("secrets/");
+    command.arg("--build");
+    command.arg("apples");
+
+    command.assert().success().stdout(predicate::str::contains(
+        r"\n### Custom\n.env.local\nsecrets/\n",
+    ));
+}
+
+#[test]
+fn build_with_dedupe() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--dedupe");
+    command.arg("--extra").arg("/apples");
+    command.arg("--build");
+    command.arg("apples");
+
+    command.assert().success().stdout(predicate::str::contains(
+        r"### Custom\n# Already in Apples.gitignore: /apples\n",
+    ));
+}
+
+#[test]
+fn build_with_conflicts() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--extra").arg("!/apples/golden");
+    command.arg("--build");
+    command.arg("apples");
+
+    command.assert().success().stdout(predicate::str::contains(
+        "Conflicting patterns in Apples.gitignore and Custom",
+    ));
+}
+
+#[test]
+fn build_to_output_directory() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--output").arg(project.path());
+    command.arg("--build");
+    command.arg("apples");
+
+    command.assert().success();
+
+    let content = read_to_string(project.path().join(".gitignore")).unwrap();
+    assert!(content.starts_with("# >>> alfred-gitignore: Apples >>>\n### Apples.gitignore\n"));
+}
+
+#[test]
+fn build_to_existing_file() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+    write(project.path().join(".gitignore"), "/target\n").unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--output").arg(project.path());
+    command.arg("--build");
+    command.arg("apples");
+
+    command.assert().success().stdout(
+        predicate::str::contains("Overwrite")
+            .and(predicate::str::contains("--force Apples"))
+            .and(predicate::str::contains("--diff Apples"))
+            .and(predicate::str::contains("-/target"))
+            .and(predicate::str::contains("Open .gitignore file").not()),
+    );
+
+    let content = read_to_string(project.path().join(".gitignore")).unwrap();
+    assert_eq!("/target\n", content);
+}
+
+#[test]
+fn build_with_diff() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+    write(project.path().join(".gitignore"), "/target\n").unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--output").arg(project.path());
+    command.arg("--merge");
+    command.arg("--diff");
+    command.arg("--dedupe");
+    command.arg("--build");
+    command.arg("apples");
+
+    command.assert().success().stdout(
+        predicate::str::contains("Changes to")
+            .and(predicate::str::contains("quicklookurl"))
+            .and(predicate::str::is_match(r"alfred-gitignore-[0-9a-f]{64}\.diff").unwrap())
+            .and(predicate::str::contains(
+                "+# >>> alfred-gitignore: Apples >>>",
+            ))
+            .and(predicate::str::contains(format!(
+                "--dedupe --output {} --merge Apples",
+                project.path().display()
+            )))
+            .and(predicate::str::contains("Open .gitignore file").not()),
+    );
+
+    let content = read_to_string(project.path().join(".gitignore")).unwrap();
+    assert_eq!("/target\n", content);
+}
+
+#[test]
+fn build_with_diff_without_changes() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+
+    Command::cargo_bin("alfred-gitignore")
+        .unwrap()
+        .arg("--repository")
+        .arg(repository.path())
+        .arg("--output")
+        .arg(project.path())
+        .arg("--build")
+        .arg("apples")
+        .assert()
+        .success();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--output").arg(project.path());
+    command.arg("--diff");
+    command.arg("--build");
+    command.arg("apples");
+
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No changes to"));
+}
+
+#[test]
+fn build_to_existing_file_with_force() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+    let destination = project.path().join("custom.gitignore");
+    write(&destination, "/target\n").unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--output").arg(&destination);
+    command.arg("--force");
+    command.arg("--build");
+    command.arg("apples");
+
+    command.assert().success();
+
+    let content = read_to_string(destination).unwrap();
+    assert!(content.starts_with("# >>> alfred-gitignore: Apples >>>\n### Apples.gitignore\n"));
+}
+
+#[test]
+fn build_and_merge_into_existing_file() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+    write(project.path().join(".gitignore"), "/target\n").unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--output").arg(project.path());
+    command.arg("--merge");
+    command.arg("--build");
+    command.arg("apples");
+
+    command.assert().success();
+
+    let content = read_to_string(project.path().join(".gitignore")).unwrap();
+    assert!(content.starts_with("/target\n\n# >>> alfred-gitignore: Apples >>>\n"));
+}
+
+#[test]
+fn build_with_project_directory() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+
+    command.env("project_directory", project.path());
+    command.arg("--repository").arg(repository.path());
+    command.arg("--build");
+    command.arg("apples");
+
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "--build --order as-typed --style full --output {} Apples",
+            project.path().display()
+        )));
+    assert!(!project.path().join(".gitignore").exists());
+}
+
+#[test]
+fn build_with_typo() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--build");
+    command.arg("apples").arg("ornages");
+
+    command.assert().success().stdout(
+        predicate::str::contains("Unknown template 'ornages'")
+            .and(predicate::str::contains("Did you mean Oranges?")),
+    );
+}
+
+#[test]
+fn build_with_exclusions() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--build");
+    command.arg("apples").arg("-/apples");
+
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("# Excluded by the query: /apples"));
+}
+
+#[test]
+fn build_with_header() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--output").arg(project.path());
+    command.arg("--header");
+    command.arg("--extra").arg(".env");
+    command.arg("--build");
+    command.arg("apples");
+
+    command.assert().success();
+
+    let content = read_to_string(project.path().join(".gitignore")).unwrap();
+    assert!(content.starts_with("# Generated by alfred-gitignore "));
+    assert!(content.contains("# Upstream: github/gitignore (unknown revision)\n"));
+    assert!(content.contains("# Templates: Apples\n"));
+    assert!(content.contains(
+        "# Regenerate: alfred-gitignore --build --order as-typed --style full --header --extra .env Apples\n"
+    ));
+    assert!(content.contains(
+        "### Apples.gitignore\n# Source: https://github.com/github/gitignore/blob/main/Apples.gitignore\n"
+    ));
+}
+
+#[test]
+fn build_minified() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--output").arg(project.path());
+    command.arg("--style").arg("minified");
+    command.arg("--build");
+    command.arg("apples");
+    command.arg("oranges");
+
+    command.assert().success();
+
+    let content = read_to_string(project.path().join(".gitignore")).unwrap();
+    assert_eq!(
+        "### Apples.gitignore\n/apples\n### Oranges.gitignore\n/oranges\n",
+        content
+    );
+}
+
+#[test]
+fn build_raw() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--build");
+    command.arg("--raw");
+    command.arg("apples");
+
+    command.assert().success().stdout(format!(
+        "# >>> alfred-gitignore: Apples >>>\n### Apples.gitignore\n{}# <<< alfred-gitignore: Apples <<<\n",
+        include_str!("files/repository/apples.gitignore")
+    ));
+}
+
+#[test]
+fn build_raw_with_typo() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--build");
+    command.arg("--raw");
+    command.arg("aples");
+
+    command
+        .assert()
+        .failure()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains(
+            "error: Unknown template 'aples', did you mean Apples?",
+        ));
+}
+
+#[test]
+fn build_raw_without_templates() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--build");
+    command.arg("--raw");
+    command.arg("cat:editor");
+
+    command
+        .assert()
+        .failure()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains("error: No templates selected"));
+}
+
+#[test]
+fn build_raw_with_invalid_order() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--build");
+    command.arg("--raw");
+    command.arg("--order").arg("random");
+    command.arg("apples");
+
+    command
+        .assert()
+        .failure()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::starts_with("error: "));
+}
+
+#[test]
+fn build_raw_with_output() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--output").arg(project.path());
+    command.arg("--build");
+    command.arg("--raw");
+    command.arg("apples");
+
+    command.assert().failure().stdout("");
+}
+
+#[test]
+fn build_with_option_after_templates() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--build");
+    command.arg("apples").arg("--raw");
+
+    command
+        .assert()
+        .failure()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains(
+            "error: Unexpected option '--raw' after the templates",
+        ));
+}
+
+#[test]
+fn build_with_unmatched_exclusion() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--build");
+    command.arg("--").arg("-/peaches").arg("apples");
+
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Unmatched exclusion '-/peaches'"));
+}
+
+#[test]
+fn build_raw_with_unmatched_exclusion() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--build").arg("--raw");
+    command.arg("apples").arg("-apples");
+
+    command.assert().success().stderr(predicate::str::contains(
+        "warning: Unmatched exclusion '-apples', Apples.gitignore has no pattern 'apples'",
+    ));
+}
+
+#[test]
+fn build_with_options_and_existing_file() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+    let output = project.path().join("My Project");
+    create_dir(&output).unwrap();
+    write(output.join(".gitignore"), "/target\n").unwrap();
+
+    let (first, second) = output.to_str().unwrap().split_once(' ').unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--order").arg("alphabetical");
+    command.arg("--style").arg("minified");
+    command.arg("--dedupe");
+    command
+        .arg("--output")
+        .arg(format!("\"{first}"))
+        .arg(format!("{second}\""));
+    command.arg("--build");
+    command.arg("oranges").arg("apples");
+
+    let arguments = format!(
+        r#"--build --order alphabetical --style minified --dedupe --output \"{}\""#,
+        output.display()
+    );
+    command.assert().success().stdout(
+        predicate::str::contains(format!("{arguments} --force Oranges Apples"))
+            .and(predicate::str::contains(format!(
+                "{arguments} --merge Oranges Apples"
+            )))
+            .and(predicate::str::contains(format!(
+                "{arguments} --diff Oranges Apples"
+            ))),
+    );
+    assert_eq!(
+        "/target\n",
+        read_to_string(output.join(".gitignore")).unwrap()
+    );
+}
+
+#[test]
+fn build_with_header_regenerates_same_content() {
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    write(repository.path().join("config"), "order = alphabetical\n").unwrap();
+    let project = TempDir::new().unwrap();
+
+    Command::cargo_bin("alfred-gitignore")
+        .unwrap()
+        .arg("--repository")
+        .arg(repository.path())
+        .arg("--output")
+        .arg(project.path())
+        .arg("--header")
+        .arg("--dedupe")
+        .arg("--extra")
+        .arg(".env")
+        .arg("--build")
+        .arg("oranges")
+        .arg("apples")
+        .assert()
+        .success();
+
+    let content = read_to_string(project.path().join(".gitignore")).unwrap();
+    let arguments = content
+        .lines()
+        .find_map(|line| line.strip_prefix("# Regenerate: alfred-gitignore "))
+        .unwrap();
+
+    // Without the configuration, the order has to come from the arguments
+    std::fs::remove_file(repository.path().join("config")).unwrap();
+
+    let output = Command::cargo_bin("alfred-gitignore")
+        .unwrap()
+        .arg("--repository")
+        .arg(repository.path())
+        .arg("--raw")
+        .args(arguments.split_whitespace())
+        .output()
+        .unwrap();
+    let regenerated = String::from_utf8(output.stdout).unwrap();
+
+    // The first line contains the date of the build
+    assert_eq!(
+        content.split_once('\n').unwrap().1,
+        regenerated.split_once('\n').unwrap().1
+    );
+}
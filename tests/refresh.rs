@@ -0,0 +1,206 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs::{create_dir, read_to_string, write, File};
+use std::io::Write;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write_templates(path: &Path) {
+    let mut apples = File::create(path.join("Apples.gitignore")).unwrap();
+    apples
+        .write_all(include_bytes!("files/repository/apples.gitignore"))
+        .unwrap();
+    apples.sync_all().unwrap();
+
+    let mut oranges = File::create(path.join("Oranges.gitignore")).unwrap();
+    oranges
+        .write_all(include_bytes!("files/repository/oranges.gitignore"))
+        .unwrap();
+    oranges.sync_all().unwrap();
+}
+
+fn build(repository: &Path, project: &Path, options: &[&str]) {
+    Command::cargo_bin("alfred-gitignore")
+        .unwrap()
+        .arg("--repository")
+        .arg(repository)
+        .arg("--output")
+        .arg(project)
+        .args(options)
+        .arg("--build")
+        .arg("apples")
+        .arg("-/apples")
+        .arg("oranges")
+        .assert()
+        .success();
+
+    let path = project.join(".gitignore");
+    let content = read_to_string(&path).unwrap();
+    write(&path, format!("/target\n\n{content}")).unwrap();
+}
+
+#[test]
+fn refresh_without_changes() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+    build(repository.path(), project.path(), &[]);
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--refresh").arg(project.path());
+
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All templates are up to date"));
+}
+
+#[test]
+fn refresh_shows_summary() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+    build(repository.path(), project.path(), &[]);
+    write(
+        repository.path().join("Oranges.gitignore"),
+        "/oranges\n/peels\n",
+    )
+    .unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--refresh").arg(project.path());
+
+    command.assert().success().stdout(
+        predicate::str::contains("Update Oranges.gitignore")
+            .and(predicate::str::contains("Lines: 1 added, 1 removed"))
+            .and(predicate::str::contains("--force"))
+            .and(predicate::str::contains("Update Apples.gitignore").not()),
+    );
+
+    let content = read_to_string(project.path().join(".gitignore")).unwrap();
+    assert!(!content.contains("/peels"));
+}
+
+#[test]
+fn refresh_with_force() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+    build(repository.path(), project.path(), &[]);
+    write(
+        repository.path().join("Oranges.gitignore"),
+        "/oranges\n/peels\n",
+    )
+    .unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--refresh").arg(project.path());
+    command.arg("--force");
+
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Updated 1 templates"));
+
+    let content = read_to_string(project.path().join(".gitignore")).unwrap();
+    assert!(content.starts_with("/target\n\n# >>> alfred-gitignore: Apples >>>\n"));
+    assert!(content.contains("# Excluded by the query: /apples\n"));
+    assert!(content.contains("/peels\n# <<< alfred-gitignore: Oranges <<<\n"));
+}
+
+#[test]
+fn refresh_with_header() {
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let project = TempDir::new().unwrap();
+    build(
+        repository.path(),
+        project.path(),
+        &["--header", "--extra", ".env"],
+    );
+
+    Command::cargo_bin("alfred-gitignore")
+        .unwrap()
+        .arg("--repository")
+        .arg(repository.path())
+        .arg("--refresh")
+        .arg(project.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All templates are up to date"));
+
+    write(repository.path().join("revision"), "2a4de26").unwrap();
+
+    Command::cargo_bin("alfred-gitignore")
+        .unwrap()
+        .arg("--repository")
+        .arg(repository.path())
+        .arg("--refresh")
+        .arg(project.path())
+        .arg("--force")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Updated 2 templates"));
+
+    let content = read_to_string(project.path().join(".gitignore")).unwrap();
+    assert!(content.contains("# Upstream: github/gitignore@2a4de26\n"));
+    assert!(content.contains("# Regenerate: alfred-gitignore --build --order as-typed --style full --header --extra .env Apples -/apples Oranges\n"));
+    assert!(content.contains(
+        "### Apples.gitignore\n# Source: https://github.com/github/gitignore/blob/2a4de26/Apples.gitignore\n"
+    ));
+    assert!(content.contains(
+        "### Oranges.gitignore\n# Source: https://github.com/github/gitignore/blob/2a4de26/Oranges.gitignore\n"
+    ));
+    assert!(content.contains("### Custom\n.env\n"));
+}
+
+#[test]
+fn refresh_with_space_in_path() {
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    let parent = TempDir::new().unwrap();
+    let project = parent.path().join("My Project");
+    create_dir(&project).unwrap();
+    build(repository.path(), &project, &[]);
+    write(
+        repository.path().join("Oranges.gitignore"),
+        "/oranges\n/peels\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("alfred-gitignore")
+        .unwrap()
+        .arg("--repository")
+        .arg(repository.path())
+        .arg("--refresh")
+        .arg(&project)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            r#"--refresh \"{}\" --force"#,
+            project.display()
+        )));
+
+    let (first, second) = project.to_str().unwrap().split_once(' ').unwrap();
+
+    Command::cargo_bin("alfred-gitignore")
+        .unwrap()
+        .arg("--repository")
+        .arg(repository.path())
+        .arg("--refresh")
+        .arg(format!("\"{first}"))
+        .arg(format!("{second}\""))
+        .arg("--force")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Updated 1 templates"));
+
+    let content = read_to_string(project.join(".gitignore")).unwrap();
+    assert!(content.contains("/peels\n"));
+}
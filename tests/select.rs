@@ -1,6 +1,6 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
-use std::fs::File;
+use std::fs::{create_dir, File};
 use std::io::Write;
 use std::path::Path;
 use tempfile::TempDir;
@@ -49,3 +49,290 @@ fn select_without_input() {
             .and(predicate::str::contains("Oranges")),
     );
 }
+
+#[test]
+fn select_with_preset() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    let mut config = File::create(repository.path().join("config")).unwrap();
+    config
+        .write_all(b"preset.Fruits = Apples Oranges\n")
+        .unwrap();
+    config.sync_all().unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("fr");
+
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Preset: Apples, Oranges"));
+}
+
+#[test]
+fn select_with_detected_templates() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    let mut templates = File::create(repository.path().join("Rust.gitignore")).unwrap();
+    templates.write_all(b"/target\n").unwrap();
+    templates.sync_all().unwrap();
+
+    let project = TempDir::new().unwrap();
+    File::create(project.path().join("Cargo.toml")).unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--detect").arg(project.path());
+    command.arg("apples");
+
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--build  Rust Apples"));
+}
+
+#[test]
+fn select_with_duplicates() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("Apples").arg("oranges").arg("apples");
+
+    command.assert().success().stdout(
+        predicate::str::contains("--build  Apples Oranges")
+            .and(predicate::str::contains("Selected more than once: Apples")),
+    );
+}
+
+#[test]
+fn select_with_typo() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("aples").arg("or");
+
+    command.assert().success().stdout(
+        predicate::str::contains("Unknown template 'aples'")
+            .and(predicate::str::contains("Did you mean Apples?"))
+            .and(predicate::str::contains("Unknown template 'or'").not()),
+    );
+}
+
+#[test]
+fn select_with_descriptions() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("o");
+
+    command.assert().success().stdout(predicate::str::contains(
+        r#""subtitle":"This file ignores oranges""#,
+    ));
+}
+
+#[test]
+fn select_with_qualified_name() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    create_dir(repository.path().join("Global")).unwrap();
+    File::create(repository.path().join("Global/Apples.gitignore")).unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("global/apples").arg("o");
+
+    command.assert().success().stdout(
+        predicate::str::contains("--build  Global/Apples")
+            .and(predicate::str::contains(" Global/Apples Oranges")),
+    );
+}
+
+#[test]
+fn select_with_exclusions() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("apples").arg("-/apples").arg("o");
+
+    command.assert().success().stdout(
+        predicate::str::contains("--build  Apples -/apples")
+            .and(predicate::str::contains("Unknown template").not())
+            .and(predicate::str::contains("Unmatched exclusion").not()),
+    );
+}
+
+#[test]
+fn select_with_unmatched_exclusion() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("apples").arg("-apples").arg("o");
+
+    command.assert().success().stdout(
+        predicate::str::contains("Unmatched exclusion '-apples'").and(predicate::str::contains(
+            "Apples.gitignore has no pattern 'apples'",
+        )),
+    );
+}
+
+#[test]
+fn select_with_category() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    create_dir(repository.path().join("Global")).unwrap();
+    File::create(repository.path().join("Global/JetBrains.gitignore")).unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("apples").arg("cat:editor");
+
+    command.assert().success().stdout(
+        predicate::str::contains(" Apples cat:editor JetBrains")
+            .and(predicate::str::contains("Oranges").not())
+            .and(predicate::str::contains("Unknown template").not()),
+    );
+}
+
+#[test]
+fn select_with_recent_builds() {
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    Command::cargo_bin("alfred-gitignore")
+        .unwrap()
+        .arg("--repository")
+        .arg(repository.path())
+        .arg("--build")
+        .arg("oranges")
+        .arg("apples")
+        .assert()
+        .success();
+
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+    command.arg("--repository").arg(repository.path());
+
+    command.assert().success().stdout(
+        predicate::str::contains(r#""title":"Oranges Apples""#)
+            .and(predicate::str::contains(
+                "Built just now to a temporary file",
+            ))
+            .and(predicate::str::contains(
+                "--build --order as-typed --style full Oranges Apples",
+            )),
+    );
+}
+
+#[test]
+fn select_with_favorite() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--favorite").arg("oranges");
+
+    command.assert().success().stdout(
+        predicate::str::is_match(r#"(?s)"title":"Oranges".*"title":"Apples""#)
+            .unwrap()
+            .and(predicate::str::contains("ToolbarFavoritesIcon.icns"))
+            .and(predicate::str::contains("favorites").not()),
+    );
+}
+
+#[test]
+fn select_with_favorite_toggle() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("oranges").arg("apples");
+
+    command.assert().success().stdout(
+        predicate::str::contains(r#""title":"Add Apples to favorites""#)
+            .and(predicate::str::contains(
+                r#""autocomplete":"--favorite Apples Oranges Apples""#,
+            ))
+            .and(predicate::str::contains("Oranges to favorites").not()),
+    );
+}
+
+#[test]
+fn select_with_unknown_favorite() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--favorite").arg("peaches");
+
+    command
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Unknown template 'peaches'"));
+}
+
+#[test]
+fn select_with_quoted_favorite() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+    File::create(repository.path().join("Visual Studio.gitignore")).unwrap();
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--favorite").arg("\"Visual").arg("Studio\"");
+    command.arg("apples").arg("\"Visual").arg("Studio\"");
+
+    command.assert().success().stdout(
+        predicate::str::contains("Remove Visual Studio from favorites")
+            .and(predicate::str::contains(
+                r#"--favorite \"Visual Studio\" Apples \"Visual Studio\""#,
+            ))
+            .and(predicate::str::contains("Unknown template").not()),
+    );
+}
+
+#[test]
+fn select_with_extras() {
+    let mut command = Command::cargo_bin("alfred-gitignore").unwrap();
+
+    let repository = TempDir::new().unwrap();
+    write_templates(repository.path());
+
+    command.arg("--repository").arg(repository.path());
+    command.arg("--extra").arg("*.log");
+    command.arg("--extra").arg("\"My").arg("Folder/\"");
+    command.arg("apples").arg("o");
+
+    command.assert().success().stdout(
+        predicate::str::contains(r#"--extra *.log --extra \"My Folder/\" Apples"#)
+            .and(predicate::str::contains("Unknown template").not()),
+    );
+}